@@ -0,0 +1,242 @@
+use bytemuck::{Pod, Zeroable};
+use wgpu::*;
+use winit::{event::ElementState, keyboard::KeyCode};
+
+// wgpu's NDC z-range is 0..1, but the perspective matrix below follows the
+// OpenGL convention of -1..1, so every projection gets fixed up through this.
+#[rustfmt::skip]
+const OPENGL_TO_WGPU_MATRIX: [[f32; 4]; 4] = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 0.5, 0.0],
+    [0.0, 0.0, 0.5, 1.0],
+];
+
+pub(crate) struct Camera {
+    pub(crate) position: [f32; 3],
+    pub(crate) yaw: f32,
+    pub(crate) pitch: f32,
+    pub(crate) aspect: f32,
+    pub(crate) fovy: f32,
+    pub(crate) znear: f32,
+    pub(crate) zfar: f32,
+}
+
+impl Camera {
+    fn forward(&self) -> [f32; 3] {
+        [
+            self.yaw.cos() * self.pitch.cos(),
+            self.pitch.sin(),
+            self.yaw.sin() * self.pitch.cos(),
+        ]
+    }
+
+    pub(crate) fn build_view_projection_matrix(&self) -> [[f32; 4]; 4] {
+        let view = look_to_rh(self.position, self.forward(), [0.0, 1.0, 0.0]);
+        let proj = perspective_rh(self.fovy, self.aspect, self.znear, self.zfar);
+        mul_mat4(&mul_mat4(&OPENGL_TO_WGPU_MATRIX, &proj), &view)
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub(crate) struct CameraUniform {
+    view_proj: [[f32; 4]; 4],
+}
+
+impl CameraUniform {
+    pub(crate) fn new() -> Self {
+        Self {
+            view_proj: IDENTITY,
+        }
+    }
+
+    pub(crate) fn update_view_proj(&mut self, camera: &Camera) {
+        self.view_proj = camera.build_view_projection_matrix();
+    }
+}
+
+const IDENTITY: [[f32; 4]; 4] = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+const MAX_PITCH: f32 = std::f32::consts::FRAC_PI_2 - 0.01;
+
+pub(crate) struct CameraController {
+    move_speed: f32,
+    mouse_sensitivity: f32,
+    is_forward_pressed: bool,
+    is_backward_pressed: bool,
+    is_left_pressed: bool,
+    is_right_pressed: bool,
+    is_up_pressed: bool,
+    is_down_pressed: bool,
+    pending_mouse_delta: (f64, f64),
+}
+
+impl CameraController {
+    pub(crate) fn new(move_speed: f32, mouse_sensitivity: f32) -> Self {
+        Self {
+            move_speed,
+            mouse_sensitivity,
+            is_forward_pressed: false,
+            is_backward_pressed: false,
+            is_left_pressed: false,
+            is_right_pressed: false,
+            is_up_pressed: false,
+            is_down_pressed: false,
+            pending_mouse_delta: (0.0, 0.0),
+        }
+    }
+
+    pub(crate) fn process_keyboard(&mut self, key: KeyCode, state: ElementState) -> bool {
+        let is_pressed = state == ElementState::Pressed;
+        match key {
+            KeyCode::KeyW | KeyCode::ArrowUp => {
+                self.is_forward_pressed = is_pressed;
+                true
+            }
+            KeyCode::KeyA | KeyCode::ArrowLeft => {
+                self.is_left_pressed = is_pressed;
+                true
+            }
+            KeyCode::KeyS | KeyCode::ArrowDown => {
+                self.is_backward_pressed = is_pressed;
+                true
+            }
+            KeyCode::KeyD | KeyCode::ArrowRight => {
+                self.is_right_pressed = is_pressed;
+                true
+            }
+            KeyCode::Space => {
+                self.is_up_pressed = is_pressed;
+                true
+            }
+            KeyCode::ShiftLeft => {
+                self.is_down_pressed = is_pressed;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Accumulates a raw mouse-motion delta to be applied on the next
+    /// `update_camera` call.
+    pub(crate) fn process_mouse_motion(&mut self, delta: (f64, f64)) {
+        self.pending_mouse_delta.0 += delta.0;
+        self.pending_mouse_delta.1 += delta.1;
+    }
+
+    pub(crate) fn update_camera(&mut self, camera: &mut Camera) {
+        let (dx, dy) = std::mem::take(&mut self.pending_mouse_delta);
+        camera.yaw += dx as f32 * self.mouse_sensitivity;
+        camera.pitch = (camera.pitch - dy as f32 * self.mouse_sensitivity)
+            .clamp(-MAX_PITCH, MAX_PITCH);
+
+        let forward = camera.forward();
+        let right = normalize(cross(forward, [0.0, 1.0, 0.0]));
+
+        if self.is_forward_pressed {
+            camera.position = add(camera.position, scale(forward, self.move_speed));
+        }
+        if self.is_backward_pressed {
+            camera.position = sub(camera.position, scale(forward, self.move_speed));
+        }
+        if self.is_right_pressed {
+            camera.position = add(camera.position, scale(right, self.move_speed));
+        }
+        if self.is_left_pressed {
+            camera.position = sub(camera.position, scale(right, self.move_speed));
+        }
+        if self.is_up_pressed {
+            camera.position[1] += self.move_speed;
+        }
+        if self.is_down_pressed {
+            camera.position[1] -= self.move_speed;
+        }
+    }
+}
+
+pub(crate) fn create_camera_bind_group_layout(device: &Device) -> BindGroupLayout {
+    device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+        label: Some("Camera Bind Group Layout"),
+        entries: &[BindGroupLayoutEntry {
+            binding: 0,
+            visibility: ShaderStages::VERTEX,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+    })
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn add(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn scale(a: [f32; 3], s: f32) -> [f32; 3] {
+    [a[0] * s, a[1] * s, a[2] * s]
+}
+
+fn length(a: [f32; 3]) -> f32 {
+    (a[0] * a[0] + a[1] * a[1] + a[2] * a[2]).sqrt()
+}
+
+fn normalize(a: [f32; 3]) -> [f32; 3] {
+    scale(a, 1.0 / length(a))
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn look_to_rh(eye: [f32; 3], direction: [f32; 3], up: [f32; 3]) -> [[f32; 4]; 4] {
+    let f = normalize(direction);
+    let s = normalize(cross(f, up));
+    let u = cross(s, f);
+
+    [
+        [s[0], u[0], -f[0], 0.0],
+        [s[1], u[1], -f[1], 0.0],
+        [s[2], u[2], -f[2], 0.0],
+        [-dot(s, eye), -dot(u, eye), dot(f, eye), 1.0],
+    ]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn perspective_rh(fovy: f32, aspect: f32, znear: f32, zfar: f32) -> [[f32; 4]; 4] {
+    let f = 1.0 / (fovy / 2.0).tan();
+    [
+        [f / aspect, 0.0, 0.0, 0.0],
+        [0.0, f, 0.0, 0.0],
+        [0.0, 0.0, (zfar + znear) / (znear - zfar), -1.0],
+        [0.0, 0.0, (2.0 * zfar * znear) / (znear - zfar), 0.0],
+    ]
+}
+
+fn mul_mat4(a: &[[f32; 4]; 4], b: &[[f32; 4]; 4]) -> [[f32; 4]; 4] {
+    let mut out = [[0.0f32; 4]; 4];
+    for (col, out_col) in out.iter_mut().enumerate() {
+        for (row, out_cell) in out_col.iter_mut().enumerate() {
+            *out_cell = (0..4).map(|k| a[k][row] * b[col][k]).sum();
+        }
+    }
+    out
+}
@@ -1,35 +1,167 @@
+use bytemuck::{Pod, Zeroable};
+use image::GenericImageView as _;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher as _};
 use pollster::block_on;
-use std::{borrow::Cow, io::Write as _, sync::Arc, time::SystemTime};
+use rayon::prelude::*;
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    sync::{Arc, mpsc},
+    time::SystemTime,
+};
 use util::{BufferInitDescriptor, DeviceExt as _};
 use wgpu::*;
 use winit::{
-    dpi::{LogicalSize, PhysicalSize},
+    dpi::{LogicalSize, PhysicalPosition, PhysicalSize},
+    event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent},
     event_loop::ActiveEventLoop,
+    keyboard::KeyCode,
     window::{Window, WindowAttributes},
 };
 
+use super::camera::{Camera, CameraController, CameraUniform, create_camera_bind_group_layout};
+
+const FRAME_TIME_HISTORY_LEN: usize = 120;
+
+// How many `CommandEncoder`s the scene pass is split across. Each worker
+// records its slice of the instanced draw on a rayon thread pool; the
+// resulting `CommandBuffer`s are then submitted together in order.
+const DEFAULT_RENDER_WORKER_COUNT: u32 = 4;
+const MAX_RENDER_WORKER_COUNT: u32 = 16;
+
+// A fullscreen triangle, generated from the vertex index so filter passes
+// don't need their own vertex/index buffers.
+const FULLSCREEN_VERT_SRC: &str = "\
+#version 450
+layout(location = 0) out vec2 v_uv;
+void main() {
+    v_uv = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2);
+    gl_Position = vec4(v_uv * 2.0 - 1.0, 0.0, 1.0);
+}
+";
+
+// Fallback used when a preset names a shader file that fails to load.
+const PASSTHROUGH_FRAG_SRC: &str = "\
+#version 450
+layout(location = 0) in vec2 v_uv;
+layout(location = 0) out vec4 out_color;
+layout(set = 0, binding = 0) uniform texture2D source_texture;
+layout(set = 0, binding = 1) uniform sampler source_sampler;
+void main() {
+    out_color = texture(sampler2D(source_texture, source_sampler), v_uv);
+}
+";
+
 #[repr(C)]
-struct Vertex([f32; 2]);
+#[derive(Copy, Clone, Pod, Zeroable)]
+struct FilterUniforms {
+    resolution: [f32; 2],
+    time: f32,
+    frame_count: u32,
+}
+
+// Fragment shader for the interactive Mandelbrot render mode: maps each
+// fragment to the complex plane around `center` at the given `zoom`, iterates
+// the escape-time series, and shades by a smooth (fractional) iteration
+// count so the coloring doesn't band. The iteration cap grows with zoom so
+// deep zooms stay detailed.
+const MANDELBROT_FRAG_SRC: &str = "\
+#version 450
+layout(location = 0) in vec2 v_uv;
+layout(location = 0) out vec4 out_color;
+layout(set = 0, binding = 0) uniform MandelbrotUniform {
+    vec2 center;
+    float zoom;
+    float time;
+} u;
+
+void main() {
+    vec2 c = u.center + (v_uv * 2.0 - 1.0) / u.zoom;
+    vec2 z = vec2(0.0);
+    float max_iter = 100.0 + 32.0 * max(log2(u.zoom), 0.0);
+    float n = 0.0;
+    for (int i = 0; i < 2000; i++) {
+        if (float(i) >= max_iter || dot(z, z) > 4.0) {
+            break;
+        }
+        z = vec2(z.x * z.x - z.y * z.y, 2.0 * z.x * z.y) + c;
+        n += 1.0;
+    }
+    if (n >= max_iter) {
+        out_color = vec4(0.0, 0.0, 0.0, 1.0);
+    } else {
+        float smooth_n = n - log2(log2(dot(z, z)) * 0.5);
+        float t = smooth_n * 0.05;
+        out_color = vec4(0.5 + 0.5 * cos(6.28318 * (t + vec3(0.0, 0.33, 0.67))), 1.0);
+    }
+}
+";
+
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+struct MandelbrotUniform {
+    center: [f32; 2],
+    zoom: f32,
+    time: f32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+struct Vertex {
+    position: [f32; 3],
+    tex_coords: [f32; 2],
+    normal: [f32; 3],
+}
 
 impl Vertex {
     fn desc() -> VertexBufferLayout<'static> {
         VertexBufferLayout {
             array_stride: std::mem::size_of::<Vertex>() as BufferAddress,
             step_mode: VertexStepMode::Vertex,
-            attributes: &[VertexAttribute {
-                offset: 0,
-                shader_location: 0,
-                format: VertexFormat::Float32x2,
-            }],
+            attributes: &[
+                VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: VertexFormat::Float32x3,
+                },
+                VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 3]>() as BufferAddress,
+                    shader_location: 1,
+                    format: VertexFormat::Float32x2,
+                },
+                VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 3]>() as BufferAddress
+                        + std::mem::size_of::<[f32; 2]>() as BufferAddress,
+                    shader_location: 2,
+                    format: VertexFormat::Float32x3,
+                },
+            ],
         }
     }
 }
 
 const VERTICES: &[Vertex] = &[
-    Vertex([-1.0, -1.0]), // Top left
-    Vertex([1.0, -1.0]),  // Top right
-    Vertex([1.0, 1.0]),   // Bottom right
-    Vertex([-1.0, 1.0]),  // Bottom left
+    Vertex {
+        position: [-1.0, -1.0, 0.0],
+        tex_coords: [0.0, 1.0],
+        normal: [0.0, 0.0, 1.0],
+    }, // Top left
+    Vertex {
+        position: [1.0, -1.0, 0.0],
+        tex_coords: [1.0, 1.0],
+        normal: [0.0, 0.0, 1.0],
+    }, // Top right
+    Vertex {
+        position: [1.0, 1.0, 0.0],
+        tex_coords: [1.0, 0.0],
+        normal: [0.0, 0.0, 1.0],
+    }, // Bottom right
+    Vertex {
+        position: [-1.0, 1.0, 0.0],
+        tex_coords: [0.0, 0.0],
+        normal: [0.0, 0.0, 1.0],
+    }, // Bottom left
 ];
 
 const INDICES: &[[u16; 3]; 2] = &[
@@ -37,6 +169,75 @@ const INDICES: &[[u16; 3]; 2] = &[
     [2, 3, 0], // Bottom left face
 ];
 
+const NUM_INSTANCES_PER_ROW: u32 = 20;
+const NUM_INSTANCES: u32 = NUM_INSTANCES_PER_ROW * NUM_INSTANCES_PER_ROW;
+const INSTANCE_SPACING: f32 = 0.2;
+
+// Per-instance model transform, packed as a 2D translation plus a rotation
+// angle instead of a full mat4 — the vertex shader builds the rotation from
+// `angle` itself, so advancing `angle` each frame is the only CPU-side work.
+#[repr(C)]
+#[derive(Copy, Clone, Pod, Zeroable)]
+struct InstanceRaw {
+    position: [f32; 2],
+    angle: f32,
+}
+
+impl InstanceRaw {
+    fn desc() -> VertexBufferLayout<'static> {
+        VertexBufferLayout {
+            array_stride: std::mem::size_of::<InstanceRaw>() as BufferAddress,
+            step_mode: VertexStepMode::Instance,
+            attributes: &[
+                VertexAttribute {
+                    offset: 0,
+                    shader_location: 3,
+                    format: VertexFormat::Float32x2,
+                },
+                VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 2]>() as BufferAddress,
+                    shader_location: 4,
+                    format: VertexFormat::Float32,
+                },
+            ],
+        }
+    }
+}
+
+struct InstanceData {
+    position: [f32; 2],
+    angle: f32,
+    angular_velocity: f32,
+}
+
+impl InstanceData {
+    fn to_raw(&self) -> InstanceRaw {
+        InstanceRaw {
+            position: self.position,
+            angle: self.angle,
+        }
+    }
+}
+
+fn create_instances() -> Vec<InstanceData> {
+    let half_extent = (NUM_INSTANCES_PER_ROW as f32 - 1.0) * INSTANCE_SPACING * 0.5;
+    (0..NUM_INSTANCES_PER_ROW)
+        .flat_map(|row| {
+            (0..NUM_INSTANCES_PER_ROW).map(move |col| {
+                let position = [
+                    col as f32 * INSTANCE_SPACING - half_extent,
+                    row as f32 * INSTANCE_SPACING - half_extent,
+                ];
+                InstanceData {
+                    position,
+                    angle: (row + col) as f32 * 0.1,
+                    angular_velocity: 0.5 + (row * NUM_INSTANCES_PER_ROW + col) as f32 * 0.01,
+                }
+            })
+        })
+        .collect()
+}
+
 #[repr(C, align(16))] // The internet says 8, but the compiler says 16.
 #[derive(Clone, Copy)]
 struct PointPosition([f32; 2]);
@@ -58,20 +259,67 @@ const STARTING_COLOR: &[PointColor; 4] = &[
     PointColor([0.0, 0.0, 1.0]), // Blue
 ];
 
+/// One stage of the post-processing filter chain: a full-screen fragment
+/// shader loaded from the path named in the preset file.
+struct FilterPass {
+    shader_path: PathBuf,
+    pipeline: RenderPipeline,
+}
+
 // The ordering of this struct is important to the program's shutdown process.
+#[allow(dead_code)]
 pub(crate) struct Context {
-    time_last_print: SystemTime,
     time_last_draw: SystemTime,
     time_start: SystemTime,
+    frame_time_history: VecDeque<f32>,
+    rotation_speed: f32,
     points_position: [PointPosition; 4],
     points_position_buffer: Buffer,
+    point_colors: [[f32; 3]; 4],
+    points_color_buffer: Buffer,
     points_bind_group: BindGroup,
     vertex_buffer: Buffer,
     index_buffer: Buffer,
+    instances: Vec<InstanceData>,
+    instance_buffer: Buffer,
     queue: Queue,
     device: Device,
+    pipeline_layout: PipelineLayout,
+    surface_format: TextureFormat,
     render_pipeline: RenderPipeline,
-    
+    shader_vert_path: PathBuf,
+    shader_frag_path: PathBuf,
+    shader_watcher: RecommendedWatcher,
+    shader_reload_rx: mpsc::Receiver<notify::Result<notify::Event>>,
+    depth_view: TextureView,
+    diffuse_texture: Texture,
+    diffuse_bind_group: BindGroup,
+    camera: Camera,
+    camera_uniform: CameraUniform,
+    camera_buffer: Buffer,
+    camera_bind_group: BindGroup,
+    camera_controller: CameraController,
+    egui_ctx: egui::Context,
+    egui_state: egui_winit::State,
+    egui_renderer: egui_wgpu::Renderer,
+    offscreen_views: [TextureView; 2],
+    filter_bind_group_layout: BindGroupLayout,
+    filter_pipeline_layout: PipelineLayout,
+    filter_sampler: Sampler,
+    filter_uniform_buffer: Buffer,
+    filter_preset_path: PathBuf,
+    filter_chain: Vec<FilterPass>,
+    frame_count: u32,
+    mandelbrot_mode: bool,
+    mandelbrot_center: [f32; 2],
+    mandelbrot_zoom: f32,
+    mandelbrot_dragging: bool,
+    mandelbrot_last_cursor: Option<(f64, f64)>,
+    mandelbrot_uniform_buffer: Buffer,
+    mandelbrot_bind_group: BindGroup,
+    mandelbrot_pipeline: RenderPipeline,
+    render_worker_count: u32,
+
     // SAFETY:
     // This MUST be dropped BEFORE window.
     // Wayland will segfault otherwise.
@@ -128,6 +376,17 @@ impl Context {
         ))
         .unwrap();
 
+        let shader_vert_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/app/shader.vert");
+        let shader_frag_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/app/shader.frag");
+
+        let (shader_reload_tx, shader_reload_rx) = mpsc::channel();
+        let mut shader_watcher = notify::recommended_watcher(shader_reload_tx).unwrap();
+        for path in [&shader_vert_path, &shader_frag_path] {
+            if let Err(error) = shader_watcher.watch(path, RecursiveMode::NonRecursive) {
+                log::warn!("Failed to watch {}: {error}", path.display());
+            }
+        }
+
         let vertex_shader = device.create_shader_module(ShaderModuleDescriptor {
             label: None,
             source: ShaderSource::Glsl {
@@ -206,26 +465,287 @@ impl Context {
             label: None,
         });
 
+        let diffuse_bytes = include_bytes!("surface.png");
+        let diffuse_image = image::load_from_memory(diffuse_bytes).unwrap();
+        let diffuse_rgba = diffuse_image.to_rgba8();
+        let (diffuse_width, diffuse_height) = diffuse_image.dimensions();
+
+        let diffuse_texture = device.create_texture(&TextureDescriptor {
+            label: Some("Diffuse Texture"),
+            size: Extent3d {
+                width: diffuse_width,
+                height: diffuse_height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba8UnormSrgb,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        Self::write_diffuse_texture(
+            &queue,
+            &diffuse_texture,
+            &diffuse_rgba,
+            diffuse_width,
+            diffuse_height,
+        );
+
+        let diffuse_view = diffuse_texture.create_view(&TextureViewDescriptor::default());
+        let diffuse_sampler = device.create_sampler(&SamplerDescriptor {
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            address_mode_w: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Nearest,
+            mipmap_filter: FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let texture_bind_group_layout =
+            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("Texture Bind Group Layout"),
+                entries: &[
+                    BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Texture {
+                            sample_type: TextureSampleType::Float { filterable: true },
+                            view_dimension: TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            });
+
+        let diffuse_bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Diffuse Bind Group"),
+            layout: &texture_bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(&diffuse_view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(&diffuse_sampler),
+                },
+            ],
+        });
+
+        let camera = Camera {
+            position: [0.0, 0.0, 2.0],
+            yaw: -std::f32::consts::FRAC_PI_2,
+            pitch: 0.0,
+            aspect: size.width as f32 / size.height as f32,
+            fovy: std::f32::consts::FRAC_PI_4,
+            znear: 0.1,
+            zfar: 100.0,
+        };
+        let mut camera_uniform = CameraUniform::new();
+        camera_uniform.update_view_proj(&camera);
+
+        let camera_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: Some("Camera Buffer"),
+            contents: bytemuck::cast_slice(&[camera_uniform]),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let camera_bind_group_layout = create_camera_bind_group_layout(&device);
+        let camera_bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Camera Bind Group"),
+            layout: &camera_bind_group_layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: camera_buffer.as_entire_binding(),
+            }],
+        });
+
+        let camera_controller = CameraController::new(0.05, 0.003);
+
         let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
             label: None,
-            bind_group_layouts: &[&points_bind_group_layout],
+            bind_group_layouts: &[
+                &points_bind_group_layout,
+                &texture_bind_group_layout,
+                &camera_bind_group_layout,
+            ],
             push_constant_ranges: &[],
         });
 
         let surface_capabilities = surface.get_capabilities(&adapter);
         let surface_format = surface_capabilities.formats[0];
 
-        let render_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
-            label: None,
-            layout: Some(&pipeline_layout),
+        let render_pipeline = Self::build_render_pipeline(
+            &device,
+            &vertex_shader,
+            &fragment_shader,
+            &pipeline_layout,
+            surface_format,
+        );
+
+        let mut config = surface
+            .get_default_config(&adapter, size.width, size.height)
+            .unwrap();
+        config.present_mode = PresentMode::Mailbox;
+        surface.configure(&device, &config);
+
+        let depth_view = Self::create_depth_view(&device, config.width, config.height);
+
+        let offscreen_views =
+            Self::create_offscreen_targets(&device, surface_format, config.width, config.height);
+
+        let filter_bind_group_layout =
+            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("Filter Bind Group Layout"),
+                entries: &[
+                    BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Texture {
+                            sample_type: TextureSampleType::Float { filterable: true },
+                            view_dimension: TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 2,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Buffer {
+                            ty: BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+
+        let filter_pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Filter Pipeline Layout"),
+            bind_group_layouts: &[&filter_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let filter_sampler = device.create_sampler(&SamplerDescriptor {
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            address_mode_w: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            mipmap_filter: FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let filter_uniform_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Filter Uniform Buffer"),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            size: std::mem::size_of::<FilterUniforms>() as u64,
+            mapped_at_creation: false,
+        });
+
+        let filter_preset_path =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("src/app/filters.preset");
+        let mut filter_chain = Self::load_filter_chain(
+            &device,
+            &filter_pipeline_layout,
+            surface_format,
+            &filter_preset_path,
+        );
+        if filter_chain.is_empty() {
+            log::warn!(
+                "No filter preset at {}; using a passthrough pass",
+                filter_preset_path.display()
+            );
+            filter_chain.push(Self::build_filter_pass(
+                &device,
+                &filter_pipeline_layout,
+                surface_format,
+                PathBuf::from("passthrough.frag"),
+            ));
+        }
+
+        let mandelbrot_uniform_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Mandelbrot Uniform Buffer"),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            size: std::mem::size_of::<MandelbrotUniform>() as u64,
+            mapped_at_creation: false,
+        });
+
+        let mandelbrot_bind_group_layout =
+            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("Mandelbrot Bind Group Layout"),
+                entries: &[BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+
+        let mandelbrot_bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Mandelbrot Bind Group"),
+            layout: &mandelbrot_bind_group_layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: mandelbrot_uniform_buffer.as_entire_binding(),
+            }],
+        });
+
+        let mandelbrot_pipeline_layout =
+            device.create_pipeline_layout(&PipelineLayoutDescriptor {
+                label: Some("Mandelbrot Pipeline Layout"),
+                bind_group_layouts: &[&mandelbrot_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        let mandelbrot_vertex_shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("Mandelbrot Vertex Shader"),
+            source: ShaderSource::Glsl {
+                shader: Cow::Borrowed(FULLSCREEN_VERT_SRC),
+                stage: naga::ShaderStage::Vertex,
+                defines: Default::default(),
+            },
+        });
+        let mandelbrot_fragment_shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("Mandelbrot Fragment Shader"),
+            source: ShaderSource::Glsl {
+                shader: Cow::Borrowed(MANDELBROT_FRAG_SRC),
+                stage: naga::ShaderStage::Fragment,
+                defines: Default::default(),
+            },
+        });
+        let mandelbrot_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Mandelbrot Pipeline"),
+            layout: Some(&mandelbrot_pipeline_layout),
             vertex: VertexState {
-                module: &vertex_shader,
+                module: &mandelbrot_vertex_shader,
                 entry_point: None,
-                buffers: &[Vertex::desc()],
+                buffers: &[],
                 compilation_options: Default::default(),
             },
             fragment: Some(FragmentState {
-                module: &fragment_shader,
+                module: &mandelbrot_fragment_shader,
                 entry_point: None,
                 compilation_options: Default::default(),
                 targets: &[Some(surface_format.into())],
@@ -237,20 +757,9 @@ impl Context {
             cache: None,
         });
 
-        let mut config = surface
-            .get_default_config(&adapter, size.width, size.height)
-            .unwrap();
-        config.present_mode = PresentMode::Mailbox;
-        surface.configure(&device, &config);
-
         let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: None,
-            contents: unsafe {
-                std::slice::from_raw_parts(
-                    VERTICES.as_ptr() as *const u8,
-                    std::mem::size_of_val(VERTICES),
-                )
-            },
+            contents: bytemuck::cast_slice(VERTICES),
             usage: BufferUsages::VERTEX,
         });
 
@@ -265,7 +774,27 @@ impl Context {
             usage: BufferUsages::INDEX,
         });
 
+        let instances = create_instances();
+        let instance_data: Vec<InstanceRaw> = instances.iter().map(InstanceData::to_raw).collect();
+        let instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Instance Buffer"),
+            contents: bytemuck::cast_slice(&instance_data),
+            usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+        });
+
         let points_position = *STARTING_POSITION;
+        let point_colors: [[f32; 3]; 4] = std::array::from_fn(|i| STARTING_COLOR[i].0);
+
+        let egui_ctx = egui::Context::default();
+        let egui_state = egui_winit::State::new(
+            egui_ctx.clone(),
+            egui::ViewportId::ROOT,
+            &window,
+            Some(window.scale_factor() as f32),
+            None,
+            None,
+        );
+        let egui_renderer = egui_wgpu::Renderer::new(&device, surface_format, None, 1, false);
 
         let time_initial = SystemTime::now();
 
@@ -275,25 +804,324 @@ impl Context {
             config,
             device,
             queue,
+            pipeline_layout,
+            surface_format,
             render_pipeline,
+            shader_vert_path,
+            shader_frag_path,
+            shader_watcher,
+            shader_reload_rx,
+            depth_view,
+            diffuse_texture,
+            diffuse_bind_group,
+            camera,
+            camera_uniform,
+            camera_buffer,
+            camera_bind_group,
+            camera_controller,
             vertex_buffer,
             index_buffer,
+            instances,
+            instance_buffer,
             points_position,
             points_position_buffer,
+            point_colors,
+            points_color_buffer,
             points_bind_group,
+            egui_ctx,
+            egui_state,
+            egui_renderer,
+            offscreen_views,
+            filter_bind_group_layout,
+            filter_pipeline_layout,
+            filter_sampler,
+            filter_uniform_buffer,
+            filter_preset_path,
+            filter_chain,
+            frame_count: 0,
+            mandelbrot_mode: false,
+            mandelbrot_center: [-0.5, 0.0],
+            mandelbrot_zoom: 0.5,
+            mandelbrot_dragging: false,
+            mandelbrot_last_cursor: None,
+            mandelbrot_uniform_buffer,
+            mandelbrot_bind_group,
+            mandelbrot_pipeline,
+            render_worker_count: DEFAULT_RENDER_WORKER_COUNT,
+            rotation_speed: std::f32::consts::TAU / 4.0,
+            frame_time_history: VecDeque::with_capacity(FRAME_TIME_HISTORY_LEN),
             time_start: time_initial,
             time_last_draw: time_initial,
-            time_last_print: time_initial,
         }
     }
 
+    fn build_render_pipeline(
+        device: &Device,
+        vertex_shader: &ShaderModule,
+        fragment_shader: &ShaderModule,
+        pipeline_layout: &PipelineLayout,
+        surface_format: TextureFormat,
+    ) -> RenderPipeline {
+        device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: None,
+            layout: Some(pipeline_layout),
+            vertex: VertexState {
+                module: vertex_shader,
+                entry_point: None,
+                buffers: &[Vertex::desc(), InstanceRaw::desc()],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(FragmentState {
+                module: fragment_shader,
+                entry_point: None,
+                compilation_options: Default::default(),
+                targets: &[Some(surface_format.into())],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: Some(DepthStencilState {
+                format: TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: CompareFunction::Less,
+                stencil: Default::default(),
+                bias: Default::default(),
+            }),
+            multisample: MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    fn create_depth_view(device: &Device, width: u32, height: u32) -> TextureView {
+        let depth_texture = device.create_texture(&TextureDescriptor {
+            label: Some("Depth Texture"),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Depth32Float,
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+
+        depth_texture.create_view(&TextureViewDescriptor::default())
+    }
+
+    fn write_diffuse_texture(
+        queue: &Queue,
+        diffuse_texture: &Texture,
+        diffuse_rgba: &[u8],
+        width: u32,
+        height: u32,
+    ) {
+        let unpadded_bytes_per_row = width * 4;
+        let align = COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(align) * align;
+
+        let mut padded_data = vec![0u8; (padded_bytes_per_row * height) as usize];
+        for row in 0..height as usize {
+            let src_start = row * unpadded_bytes_per_row as usize;
+            let dst_start = row * padded_bytes_per_row as usize;
+            let src_end = src_start + unpadded_bytes_per_row as usize;
+            let dst_end = dst_start + unpadded_bytes_per_row as usize;
+            padded_data[dst_start..dst_end].copy_from_slice(&diffuse_rgba[src_start..src_end]);
+        }
+
+        queue.write_texture(
+            TexelCopyTextureInfo {
+                texture: diffuse_texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            &padded_data,
+            TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_bytes_per_row),
+                rows_per_image: Some(height),
+            },
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+
+    fn create_offscreen_targets(
+        device: &Device,
+        format: TextureFormat,
+        width: u32,
+        height: u32,
+    ) -> [TextureView; 2] {
+        std::array::from_fn(|i| {
+            device
+                .create_texture(&TextureDescriptor {
+                    label: Some(if i == 0 { "Offscreen Texture A" } else { "Offscreen Texture B" }),
+                    size: Extent3d { width, height, depth_or_array_layers: 1 },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format,
+                    usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                })
+                .create_view(&TextureViewDescriptor::default())
+        })
+    }
+
+    /// Reads the filter chain preset: one fragment-shader path per line,
+    /// blank lines and `#` comments ignored. Missing presets yield an empty
+    /// chain, which the caller falls back to a passthrough pass for.
+    fn load_filter_chain(
+        device: &Device,
+        pipeline_layout: &PipelineLayout,
+        surface_format: TextureFormat,
+        preset_path: &Path,
+    ) -> Vec<FilterPass> {
+        let Ok(contents) = std::fs::read_to_string(preset_path) else {
+            return Vec::new();
+        };
+
+        contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let shader_path = PathBuf::from(line);
+                Self::build_filter_pass(device, pipeline_layout, surface_format, shader_path)
+            })
+            .collect()
+    }
+
+    fn build_filter_pass(
+        device: &Device,
+        pipeline_layout: &PipelineLayout,
+        surface_format: TextureFormat,
+        shader_path: PathBuf,
+    ) -> FilterPass {
+        let source = std::fs::read_to_string(&shader_path).unwrap_or_else(|error| {
+            log::warn!(
+                "Failed to read filter shader {}: {error}; using a passthrough",
+                shader_path.display()
+            );
+            PASSTHROUGH_FRAG_SRC.to_string()
+        });
+
+        let vertex_shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("Filter Vertex Shader"),
+            source: ShaderSource::Glsl {
+                shader: Cow::Borrowed(FULLSCREEN_VERT_SRC),
+                stage: naga::ShaderStage::Vertex,
+                defines: Default::default(),
+            },
+        });
+        let fragment_shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("Filter Fragment Shader"),
+            source: ShaderSource::Glsl {
+                shader: Cow::Owned(source),
+                stage: naga::ShaderStage::Fragment,
+                defines: Default::default(),
+            },
+        });
+
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Filter Pipeline"),
+            layout: Some(pipeline_layout),
+            vertex: VertexState {
+                module: &vertex_shader,
+                entry_point: None,
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(FragmentState {
+                module: &fragment_shader,
+                entry_point: None,
+                compilation_options: Default::default(),
+                targets: &[Some(surface_format.into())],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        FilterPass { shader_path, pipeline }
+    }
+
+    /// Polls the shader watcher and, on a modification event, recompiles the
+    /// GLSL modules and rebuilds `render_pipeline` in place. Compile/validation
+    /// failures are logged and the last-good pipeline stays live.
+    pub(crate) fn poll_shader_reload(&mut self) -> bool {
+        let mut reload_requested = false;
+        while let Ok(event) = self.shader_reload_rx.try_recv() {
+            if matches!(event, Ok(event) if event.kind.is_modify()) {
+                reload_requested = true;
+            }
+        }
+
+        if !reload_requested {
+            return false;
+        }
+
+        let (Ok(vert_source), Ok(frag_source)) = (
+            std::fs::read_to_string(&self.shader_vert_path),
+            std::fs::read_to_string(&self.shader_frag_path),
+        ) else {
+            return true;
+        };
+
+        self.device.push_error_scope(ErrorFilter::Validation);
+
+        let vertex_shader = self.device.create_shader_module(ShaderModuleDescriptor {
+            label: None,
+            source: ShaderSource::Glsl {
+                shader: Cow::Owned(vert_source),
+                stage: naga::ShaderStage::Vertex,
+                defines: Default::default(),
+            },
+        });
+        let fragment_shader = self.device.create_shader_module(ShaderModuleDescriptor {
+            label: None,
+            source: ShaderSource::Glsl {
+                shader: Cow::Owned(frag_source),
+                stage: naga::ShaderStage::Fragment,
+                defines: Default::default(),
+            },
+        });
+        let render_pipeline = Self::build_render_pipeline(
+            &self.device,
+            &vertex_shader,
+            &fragment_shader,
+            &self.pipeline_layout,
+            self.surface_format,
+        );
+
+        if let Some(error) = block_on(self.device.pop_error_scope()) {
+            log::error!("Shader reload failed, keeping previous pipeline: {error}");
+        } else {
+            self.render_pipeline = render_pipeline;
+            log::info!("Reloaded shader.vert/shader.frag");
+        }
+
+        true
+    }
+
     pub(crate) fn redraw(&mut self) {
-        let r = -SystemTime::now()
-            .duration_since(self.time_start)
-            .unwrap()
-            .as_secs_f32()
-            * std::f32::consts::TAU
-            / 4.0;
+        self.camera_controller.update_camera(&mut self.camera);
+        self.camera_uniform.update_view_proj(&self.camera);
+        self.queue.write_buffer(
+            &self.camera_buffer,
+            0,
+            bytemuck::cast_slice(&[self.camera_uniform]),
+        );
+
+        let r = -SystemTime::now().duration_since(self.time_start).unwrap().as_secs_f32()
+            * self.rotation_speed;
         let mut sin_r = r.sin();
         let mut cos_r = r.cos();
         let scale_factor = (sin_r + 1.0) / 2.0;
@@ -313,25 +1141,259 @@ impl Context {
                 )
             });
 
+        let point_colors: [PointColor; 4] =
+            std::array::from_fn(|i| PointColor(self.point_colors[i]));
+        self.queue
+            .write_buffer(&self.points_color_buffer, 0, unsafe {
+                std::slice::from_raw_parts(
+                    point_colors.as_ptr() as *const u8,
+                    std::mem::size_of_val(&point_colors),
+                )
+            });
+
+        let dt = SystemTime::now().duration_since(self.time_last_draw).unwrap().as_secs_f32();
+        for instance in &mut self.instances {
+            instance.angle += instance.angular_velocity * dt;
+        }
+        let instance_data: Vec<InstanceRaw> =
+            self.instances.iter().map(InstanceData::to_raw).collect();
+        self.queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&instance_data));
+
         let frame = self.surface.get_current_texture().unwrap();
         let view = frame.texture.create_view(&TextureViewDescriptor::default());
         let mut encoder = self
             .device
             .create_command_encoder(&CommandEncoderDescriptor { label: None });
+        let mut command_buffers = Vec::new();
 
-        {
-            let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
-                label: None,
+        if self.mandelbrot_mode {
+            let mandelbrot_uniform = MandelbrotUniform {
+                center: self.mandelbrot_center,
+                zoom: self.mandelbrot_zoom,
+                time: SystemTime::now().duration_since(self.time_start).unwrap().as_secs_f32(),
+            };
+            self.queue.write_buffer(
+                &self.mandelbrot_uniform_buffer,
+                0,
+                bytemuck::bytes_of(&mandelbrot_uniform),
+            );
+            self.render_mandelbrot(&mut encoder, &view);
+        } else {
+            command_buffers.extend(self.render_scene_parallel());
+            self.render_filter_chain(&mut encoder, &view);
+        }
+
+        self.draw_egui(&mut encoder, &view);
+        command_buffers.push(encoder.finish());
+
+        self.queue.submit(command_buffers);
+        frame.present();
+
+        let time_current = SystemTime::now();
+        let frame_time = time_current.duration_since(self.time_last_draw).unwrap().as_secs_f32();
+        self.frame_time_history.push_back(frame_time);
+        if self.frame_time_history.len() > FRAME_TIME_HISTORY_LEN {
+            self.frame_time_history.pop_front();
+        }
+        self.time_last_draw = time_current;
+
+        self.window.request_redraw();
+    }
+
+    /// Records the instanced scene draw across `render_worker_count` worker
+    /// threads, one `CommandEncoder` per slice of instances. The encoders are
+    /// recorded in parallel on a rayon thread pool, but the returned
+    /// `CommandBuffer`s are ordered so the first clears the color/depth
+    /// attachments and the rest load onto what the previous one drew.
+    fn render_scene_parallel(&self) -> Vec<CommandBuffer> {
+        let worker_count = self.render_worker_count.max(1);
+        let instances_per_worker = NUM_INSTANCES.div_ceil(worker_count);
+
+        // `Context` holds a `mpsc::Receiver` for shader-reload events, which
+        // isn't `Sync`, so the parallel closure below can't capture `&self`
+        // directly. Pull out just the `Sync` resources it needs instead.
+        struct SceneWorkerResources<'a> {
+            device: &'a Device,
+            color_view: &'a TextureView,
+            depth_view: &'a TextureView,
+            pipeline: &'a RenderPipeline,
+            points_bind_group: &'a BindGroup,
+            diffuse_bind_group: &'a BindGroup,
+            camera_bind_group: &'a BindGroup,
+            vertex_buffer: &'a Buffer,
+            instance_buffer: &'a Buffer,
+            index_buffer: &'a Buffer,
+        }
+        let resources = SceneWorkerResources {
+            device: &self.device,
+            color_view: &self.offscreen_views[0],
+            depth_view: &self.depth_view,
+            pipeline: &self.render_pipeline,
+            points_bind_group: &self.points_bind_group,
+            diffuse_bind_group: &self.diffuse_bind_group,
+            camera_bind_group: &self.camera_bind_group,
+            vertex_buffer: &self.vertex_buffer,
+            instance_buffer: &self.instance_buffer,
+            index_buffer: &self.index_buffer,
+        };
+
+        (0..worker_count)
+            .into_par_iter()
+            .map(|worker| {
+                let start = (worker * instances_per_worker).min(NUM_INSTANCES);
+                let end = ((worker + 1) * instances_per_worker).min(NUM_INSTANCES);
+
+                let mut encoder =
+                    resources.device.create_command_encoder(&CommandEncoderDescriptor {
+                        label: Some("Scene Worker Encoder"),
+                    });
+                {
+                    let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                        label: Some("Scene Worker Pass"),
+                        color_attachments: &[Some(RenderPassColorAttachment {
+                            view: resources.color_view,
+                            resolve_target: None,
+                            ops: Operations {
+                                load: if worker == 0 {
+                                    LoadOp::Clear(Color { r: 0.1, g: 0.2, b: 0.3, a: 1.0 })
+                                } else {
+                                    LoadOp::Load
+                                },
+                                store: StoreOp::Store,
+                            },
+                        })],
+                        depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                            view: resources.depth_view,
+                            depth_ops: Some(Operations {
+                                load: if worker == 0 { LoadOp::Clear(1.0) } else { LoadOp::Load },
+                                store: StoreOp::Store,
+                            }),
+                            stencil_ops: None,
+                        }),
+                        timestamp_writes: None,
+                        occlusion_query_set: None,
+                    });
+
+                    if start < end {
+                        render_pass.set_pipeline(resources.pipeline);
+                        render_pass.set_bind_group(0, resources.points_bind_group, &[]);
+                        render_pass.set_bind_group(1, resources.diffuse_bind_group, &[]);
+                        render_pass.set_bind_group(2, resources.camera_bind_group, &[]);
+                        render_pass.set_vertex_buffer(0, resources.vertex_buffer.slice(..));
+                        render_pass.set_vertex_buffer(1, resources.instance_buffer.slice(..));
+                        render_pass.set_index_buffer(
+                            resources.index_buffer.slice(..),
+                            IndexFormat::Uint16,
+                        );
+                        render_pass.draw_indexed(0..(INDICES.len() * 3) as u32, 0, start..end);
+                    }
+                }
+                encoder.finish()
+            })
+            .collect()
+    }
+
+    fn render_mandelbrot(&mut self, encoder: &mut CommandEncoder, view: &TextureView) {
+        let mut mandelbrot_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Mandelbrot Pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(Color::BLACK),
+                    store: StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        mandelbrot_pass.set_pipeline(&self.mandelbrot_pipeline);
+        mandelbrot_pass.set_bind_group(0, &self.mandelbrot_bind_group, &[]);
+        mandelbrot_pass.draw(0..3, 0..1);
+    }
+
+    /// Toggles between the normal scene and the interactive Mandelbrot view.
+    pub(crate) fn toggle_mandelbrot_mode(&mut self) {
+        self.mandelbrot_mode = !self.mandelbrot_mode;
+    }
+
+    pub(crate) fn handle_mandelbrot_mouse_input(
+        &mut self,
+        state: ElementState,
+        button: MouseButton,
+    ) {
+        if button == MouseButton::Left {
+            self.mandelbrot_dragging = state == ElementState::Pressed;
+            if !self.mandelbrot_dragging {
+                self.mandelbrot_last_cursor = None;
+            }
+        }
+    }
+
+    pub(crate) fn handle_mandelbrot_cursor_moved(&mut self, position: PhysicalPosition<f64>) {
+        if self.mandelbrot_mode && self.mandelbrot_dragging {
+            if let Some((last_x, last_y)) = self.mandelbrot_last_cursor {
+                let scale = 2.0 / (self.mandelbrot_zoom * self.config.height as f32);
+                self.mandelbrot_center[0] -= (position.x - last_x) as f32 * scale;
+                self.mandelbrot_center[1] += (position.y - last_y) as f32 * scale;
+            }
+        }
+        self.mandelbrot_last_cursor = Some((position.x, position.y));
+    }
+
+    pub(crate) fn handle_mandelbrot_mouse_wheel(&mut self, delta: MouseScrollDelta) {
+        if !self.mandelbrot_mode {
+            return;
+        }
+        let scroll = match delta {
+            MouseScrollDelta::LineDelta(_, y) => y,
+            MouseScrollDelta::PixelDelta(position) => (position.y / 20.0) as f32,
+        };
+        self.mandelbrot_zoom = (self.mandelbrot_zoom * 1.1f32.powf(scroll)).max(0.1);
+    }
+
+    fn render_filter_chain(&mut self, encoder: &mut CommandEncoder, view: &TextureView) {
+        let uniforms = FilterUniforms {
+            resolution: [self.config.width as f32, self.config.height as f32],
+            time: SystemTime::now().duration_since(self.time_start).unwrap().as_secs_f32(),
+            frame_count: self.frame_count,
+        };
+        self.queue.write_buffer(&self.filter_uniform_buffer, 0, bytemuck::bytes_of(&uniforms));
+        self.frame_count = self.frame_count.wrapping_add(1);
+
+        let pass_count = self.filter_chain.len();
+        let mut source_index = 0;
+        for (i, pass) in self.filter_chain.iter().enumerate() {
+            let is_last = i == pass_count - 1;
+            let target_view = if is_last { view } else { &self.offscreen_views[1 - source_index] };
+
+            let bind_group = self.device.create_bind_group(&BindGroupDescriptor {
+                label: Some("Filter Bind Group"),
+                layout: &self.filter_bind_group_layout,
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: BindingResource::TextureView(&self.offscreen_views[source_index]),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::Sampler(&self.filter_sampler),
+                    },
+                    BindGroupEntry {
+                        binding: 2,
+                        resource: self.filter_uniform_buffer.as_entire_binding(),
+                    },
+                ],
+            });
+
+            let mut filter_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Filter Pass"),
                 color_attachments: &[Some(RenderPassColorAttachment {
-                    view: &view,
+                    view: target_view,
                     resolve_target: None,
                     ops: Operations {
-                        load: LoadOp::Clear(Color {
-                            r: 0.1,
-                            g: 0.2,
-                            b: 0.3,
-                            a: 1.0,
-                        }),
+                        load: LoadOp::Clear(Color::BLACK),
                         store: StoreOp::Store,
                     },
                 })],
@@ -339,40 +1401,149 @@ impl Context {
                 timestamp_writes: None,
                 occlusion_query_set: None,
             });
+            filter_pass.set_pipeline(&pass.pipeline);
+            filter_pass.set_bind_group(0, &bind_group, &[]);
+            filter_pass.draw(0..3, 0..1);
+            drop(filter_pass);
 
-            render_pass.set_pipeline(&self.render_pipeline);
-            render_pass.set_bind_group(0, &self.points_bind_group, &[]);
-            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-            render_pass.set_index_buffer(self.index_buffer.slice(..), IndexFormat::Uint16);
-            render_pass.draw_indexed(0..(INDICES.len() * 3) as u32, 0, 0..1);
+            if !is_last {
+                source_index = 1 - source_index;
+            }
         }
+    }
 
-        self.queue.submit(Some(encoder.finish()));
-        frame.present();
+    fn draw_egui(&mut self, encoder: &mut CommandEncoder, view: &TextureView) {
+        let mut point_colors = self.point_colors;
+        let mut rotation_speed = self.rotation_speed;
+        let mut render_worker_count = self.render_worker_count;
+        let frame_times: Vec<f32> = self.frame_time_history.iter().copied().collect();
+
+        let raw_input = self.egui_state.take_egui_input(&self.window);
+        let full_output = self.egui_ctx.run(raw_input, |ctx| {
+            egui::Window::new("Debug").show(ctx, |ui| {
+                let fps = frame_times.last().map_or(0.0, |dt| 1.0 / dt);
+                ui.label(format!("{fps:.1} fps"));
+                let (_, rect) = ui.allocate_space(egui::vec2(ui.available_width(), 48.0));
+                let max_frame_time = frame_times.iter().copied().fold(0.0f32, f32::max);
+                if max_frame_time > 0.0 {
+                    let points: Vec<egui::Pos2> = frame_times
+                        .iter()
+                        .enumerate()
+                        .map(|(i, &dt)| {
+                            let x = rect.left()
+                                + rect.width() * (i as f32 / FRAME_TIME_HISTORY_LEN as f32);
+                            let y = rect.bottom() - rect.height() * (dt / max_frame_time).min(1.0);
+                            egui::pos2(x, y)
+                        })
+                        .collect();
+                    ui.painter().line(points, egui::Stroke::new(1.5, egui::Color32::GREEN));
+                }
+
+                ui.separator();
+                ui.label("Point colors");
+                for (i, color) in point_colors.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Point {i}"));
+                        ui.color_edit_button_rgb(color);
+                    });
+                }
+
+                ui.separator();
+                ui.add(
+                    egui::Slider::new(&mut rotation_speed, 0.0..=std::f32::consts::TAU)
+                        .text("Rotation speed (rad/s)"),
+                );
+
+                ui.separator();
+                ui.add(
+                    egui::Slider::new(&mut render_worker_count, 1..=MAX_RENDER_WORKER_COUNT)
+                        .text("Scene render workers"),
+                );
+            });
+        });
+        self.point_colors = point_colors;
+        self.rotation_speed = rotation_speed;
+        self.render_worker_count = render_worker_count;
+
+        self.egui_state.handle_platform_output(&self.window, full_output.platform_output);
+        let clipped_primitives =
+            self.egui_ctx.tessellate(full_output.shapes, full_output.pixels_per_point);
+
+        for (id, delta) in &full_output.textures_delta.set {
+            self.egui_renderer.update_texture(&self.device, &self.queue, *id, delta);
+        }
+
+        let screen_descriptor = egui_wgpu::ScreenDescriptor {
+            size_in_pixels: [self.config.width, self.config.height],
+            pixels_per_point: full_output.pixels_per_point,
+        };
+        self.egui_renderer.update_buffers(
+            &self.device,
+            &self.queue,
+            encoder,
+            &clipped_primitives,
+            &screen_descriptor,
+        );
 
-        let time_current = SystemTime::now();
-        if time_current.duration_since(self.time_last_print).unwrap()
-            > std::time::Duration::from_millis(50)
         {
-            print!(
-                "\x1b[s{:7.1}\x1b[u",
-                1.0 / time_current
-                    .duration_since(self.time_last_draw)
-                    .unwrap()
-                    .as_secs_f32()
+            let egui_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("egui pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Load,
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            self.egui_renderer.render(
+                &mut egui_pass.forget_lifetime(),
+                &clipped_primitives,
+                &screen_descriptor,
             );
-            std::io::stdout().flush().unwrap();
-            self.time_last_print = time_current;
         }
-        self.time_last_draw = time_current;
 
-        self.window.request_redraw();
+        for id in &full_output.textures_delta.free {
+            self.egui_renderer.free_texture(id);
+        }
+    }
+
+    /// Forwards a window event to egui so overlay widgets stay interactive.
+    pub(crate) fn handle_egui_event(&mut self, event: &WindowEvent) -> bool {
+        self.egui_state.on_window_event(&self.window, event).consumed
     }
 
     pub(crate) fn resize(&mut self, new_size: PhysicalSize<u32>) {
         self.config.width = new_size.width.max(1);
         self.config.height = new_size.height.max(1);
         self.surface.configure(&self.device, &self.config);
+
+        self.offscreen_views = Self::create_offscreen_targets(
+            &self.device,
+            self.surface_format,
+            self.config.width,
+            self.config.height,
+        );
+        self.depth_view =
+            Self::create_depth_view(&self.device, self.config.width, self.config.height);
+        self.camera.aspect = self.config.width as f32 / self.config.height as f32;
+
         self.window.request_redraw();
     }
+
+    pub(crate) fn window(&self) -> &Window {
+        &self.window
+    }
+
+    pub(crate) fn process_keyboard(&mut self, key: KeyCode, state: ElementState) -> bool {
+        self.camera_controller.process_keyboard(key, state)
+    }
+
+    pub(crate) fn process_mouse_motion(&mut self, delta: (f64, f64)) {
+        self.camera_controller.process_mouse_motion(delta);
+    }
 }
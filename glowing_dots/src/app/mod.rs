@@ -0,0 +1,81 @@
+use winit::{
+    application::ApplicationHandler,
+    event::{DeviceEvent, DeviceId, WindowEvent},
+    event_loop::ActiveEventLoop,
+    keyboard::{KeyCode, PhysicalKey},
+    window::WindowId,
+};
+
+const MANDELBROT_TOGGLE_KEY: KeyCode = KeyCode::KeyM;
+
+mod camera;
+mod context;
+use context::Context;
+
+#[derive(Default)]
+pub(crate) struct Application {
+    context: Option<Context>,
+}
+
+impl ApplicationHandler for Application {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.context.is_none() {
+            self.context = Some(Context::new(event_loop));
+        }
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        _window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        let context = self.context.as_mut().unwrap();
+        if context.poll_shader_reload() {
+            context.window().request_redraw();
+        }
+        let consumed_by_egui = context.handle_egui_event(&event);
+        match event {
+            WindowEvent::Resized(new_size) => context.resize(new_size),
+            WindowEvent::RedrawRequested => context.redraw(),
+            WindowEvent::KeyboardInput { event, .. } if !consumed_by_egui => {
+                if event.physical_key == PhysicalKey::Code(KeyCode::Escape) && !event.repeat {
+                    event_loop.exit();
+                } else if event.physical_key == PhysicalKey::Code(MANDELBROT_TOGGLE_KEY)
+                    && !event.repeat
+                {
+                    context.toggle_mandelbrot_mode();
+                    context.window().request_redraw();
+                } else if let PhysicalKey::Code(key) = event.physical_key {
+                    if context.process_keyboard(key, event.state) {
+                        context.window().request_redraw();
+                    }
+                }
+            }
+            WindowEvent::MouseInput { state, button, .. } if !consumed_by_egui => {
+                context.handle_mandelbrot_mouse_input(state, button);
+            }
+            WindowEvent::CursorMoved { position, .. } if !consumed_by_egui => {
+                context.handle_mandelbrot_cursor_moved(position);
+                context.window().request_redraw();
+            }
+            WindowEvent::MouseWheel { delta, .. } if !consumed_by_egui => {
+                context.handle_mandelbrot_mouse_wheel(delta);
+                context.window().request_redraw();
+            }
+            WindowEvent::CloseRequested => event_loop.exit(),
+            _ => {}
+        };
+    }
+
+    fn device_event(
+        &mut self,
+        _event_loop: &ActiveEventLoop,
+        _device_id: DeviceId,
+        event: DeviceEvent,
+    ) {
+        if let (Some(context), DeviceEvent::MouseMotion { delta }) = (&mut self.context, event) {
+            context.process_mouse_motion(delta);
+        }
+    }
+}
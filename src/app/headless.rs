@@ -0,0 +1,99 @@
+// Headless offline rendering: drives the same compute-and-readback path as
+// the windowed `Context`, but with no `Surface`/`Window` and a fixed
+// timestep clock, so `--headless --frames N --out DIR` produces a
+// deterministic, reproducible PNG sequence with no vsync or presentation.
+
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+use wgpu::*;
+
+use super::render_core::{self, RenderCore};
+
+const WIDTH: u32 = 500;
+const HEIGHT: u32 = 500;
+const FPS: f32 = 60.0;
+
+pub fn run_headless(frames: u32, out_dir: PathBuf, frames_in_flight: usize) {
+    std::fs::create_dir_all(&out_dir).unwrap();
+
+    let instance = Instance::new(&InstanceDescriptor {
+        backends: Backends::PRIMARY,
+        flags: InstanceFlags::from_env_or_default(),
+        backend_options: BackendOptions::from_env_or_default(),
+    });
+
+    let adapter = pollster::block_on(instance.request_adapter(&RequestAdapterOptions {
+        power_preference: PowerPreference::default(),
+        force_fallback_adapter: false,
+        compatible_surface: None,
+    }))
+    .unwrap();
+
+    let required_features = adapter.features() & Features::TIMESTAMP_QUERY;
+    let (device, queue) = pollster::block_on(adapter.request_device(
+        &DeviceDescriptor {
+            label: None,
+            required_features,
+            required_limits: Limits::default().using_resolution(adapter.limits()),
+            memory_hints: MemoryHints::MemoryUsage,
+        },
+        None,
+    ))
+    .unwrap();
+
+    let mut core = RenderCore::new(device, queue, WIDTH, HEIGHT, frames_in_flight);
+
+    for frame_index in 0..frames {
+        let elapsed_secs = frame_index as f32 / FPS;
+
+        let (mut encoder, index) = core.begin_frame(elapsed_secs);
+        let capture = core.begin_frame_capture(&mut encoder, index);
+        core.submit_frame(encoder, index);
+
+        let pixels = map_frame_capture_blocking(&core.device, capture);
+
+        let path = out_dir.join(format!("frame_{frame_index:05}.png"));
+        image::save_buffer(&path, &pixels, WIDTH, HEIGHT, image::ColorType::Rgba8).unwrap();
+
+        println!("wrote {}", path.display());
+    }
+}
+
+// Blocks the calling thread until `capture`'s buffer finishes mapping, then
+// strips the row padding and returns tightly-packed RGBA8 pixels. Headless
+// rendering writes one frame at a time, so there's no render loop to keep
+// responsive the way the windowed screenshot path needs to stay non-blocking.
+fn map_frame_capture_blocking(device: &Device, capture: render_core::FrameCapture) -> Vec<u8> {
+    let mapped_result = Arc::new(Mutex::new(None));
+    let mapped_result_callback = mapped_result.clone();
+    capture
+        .buffer
+        .slice(..)
+        .map_async(MapMode::Read, move |result| {
+            *mapped_result_callback.lock().unwrap() = Some(result);
+        });
+
+    while mapped_result.lock().unwrap().is_none() {
+        device.poll(Maintain::Wait);
+    }
+    mapped_result
+        .lock()
+        .unwrap()
+        .take()
+        .unwrap()
+        .expect("failed to map frame capture buffer");
+
+    let pixels = {
+        let mapped = capture.buffer.slice(..).get_mapped_range();
+        render_core::unpad_rgba8(
+            &mapped,
+            capture.width,
+            capture.height,
+            capture.padded_bytes_per_row,
+        )
+    };
+    capture.buffer.unmap();
+    pixels
+}
@@ -8,7 +8,11 @@ use winit::{
 };
 
 mod context;
+mod headless;
+mod render_core;
 use context::Context;
+pub use headless::run_headless;
+pub(crate) use render_core::{parse_arg_value, DEFAULT_FRAMES_IN_FLIGHT};
 
 #[derive(Default)]
 pub struct Application {
@@ -47,6 +51,9 @@ impl ApplicationHandler for Application {
                         PhysicalKey::Code(KeyCode::F11) => {
                             context.toggle_fullscreen();
                         }
+                        PhysicalKey::Code(KeyCode::F2) => {
+                            context.request_screenshot();
+                        }
                         _ => {}
                     }
                 }
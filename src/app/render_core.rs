@@ -0,0 +1,681 @@
+// The surface/window-independent half of the renderer: device, queue,
+// compute pipeline, frame resources and shader hot-reload. Shared by the
+// windowed `Context` (which blits each frame to a swapchain) and the
+// headless offline renderer (which reads each frame back to disk).
+
+use pollster::block_on;
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    num::NonZero,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+use wgpu::*;
+
+pub(crate) const DEFAULT_FRAMES_IN_FLIGHT: usize = 2;
+
+#[repr(C, align(16))] // The internet says 8, but the compiler says 16.
+#[derive(Clone, Copy)]
+struct PointPosition([f32; 2]);
+
+const STARTING_POSITION: &[PointPosition; 4] = &[
+    PointPosition([-0.5, -0.5]), // White
+    PointPosition([0.5, -0.5]),  // Red
+    PointPosition([0.5, 0.5]),   // Green
+    PointPosition([-0.5, 0.5]),  // Blue
+];
+
+// GPU-side timing for a single `FrameData` slot: a two-entry timestamp query
+// set bracketing the compute pass, resolved into a readback buffer. Mapping
+// is kicked off non-blockingly right after submission, so the ticks are only
+// consumed the *next* time this slot comes back around.
+struct FrameGpuTiming {
+    query_set: QuerySet,
+    resolve_buffer: Buffer,
+    readback_buffer: Arc<Buffer>,
+    pending_ticks: Arc<Mutex<Option<[u64; 2]>>>,
+}
+
+pub(crate) struct FrameData {
+    points_position_buffer: Buffer,
+    pub(crate) texture: Texture,
+    bind_group: BindGroup,
+    gpu_timing: Option<FrameGpuTiming>,
+}
+
+// A readback buffer mid-flight for an in-progress frame capture: allocated
+// and copied into during the frame it was requested, then mapped by the
+// caller once that copy has landed on the GPU. `begin_frame_capture` and
+// `unpad_rgba8` are shared by the on-demand screenshot and the headless
+// renderer; each caller decides whether to map non-blockingly or wait.
+pub(crate) struct FrameCapture {
+    pub(crate) buffer: Arc<Buffer>,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) padded_bytes_per_row: u32,
+}
+
+pub(crate) struct RenderCore {
+    pub(crate) device: Device,
+    pub(crate) queue: Queue,
+
+    bind_group_layout: BindGroupLayout,
+    frame_data: Vec<FrameData>,
+    frame_data_index: usize,
+    // The submission each slot was last written to in, if its frame hasn't
+    // been waited on yet. Checked in `begin_frame` before the CPU reuses a
+    // slot's buffers, so a deeper in-flight count than 2 can't corrupt a
+    // uniform the GPU is still reading.
+    in_flight_submissions: Vec<Option<SubmissionIndex>>,
+
+    timestamp_period_ns: f32,
+    last_compute_time_ms: Option<f32>,
+
+    compute_pipeline: ComputePipeline,
+    compute_shader_path: PathBuf,
+    shader_source_mtimes: HashMap<PathBuf, SystemTime>,
+    last_shader_poll: SystemTime,
+
+    width: u32,
+    height: u32,
+}
+
+impl RenderCore {
+    pub(crate) fn new(
+        device: Device,
+        queue: Queue,
+        width: u32,
+        height: u32,
+        frames_in_flight: usize,
+    ) -> Self {
+        assert!(frames_in_flight > 0, "frames_in_flight must be at least 1");
+
+        let bind_group_layout = Self::create_bind_group_layout(&device);
+
+        let gpu_timing_supported = device.features().contains(Features::TIMESTAMP_QUERY);
+        let timestamp_period_ns = queue.get_timestamp_period();
+
+        let frame_data = Self::create_frame_data(
+            &device,
+            width,
+            height,
+            &bind_group_layout,
+            gpu_timing_supported,
+            frames_in_flight,
+        );
+
+        let compute_shader_path =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("src/app/shader.comp.glsl");
+        let (compute_pipeline, shader_source_mtimes) = match Self::try_build_compute_pipeline(
+            &device,
+            &bind_group_layout,
+            &compute_shader_path,
+        ) {
+            Ok(built) => built,
+            Err(error) => panic!("failed to compile initial compute shader: {error}"),
+        };
+
+        Self {
+            device,
+            queue,
+            bind_group_layout,
+            in_flight_submissions: vec![None; frame_data.len()],
+            frame_data,
+            frame_data_index: 0,
+            timestamp_period_ns,
+            last_compute_time_ms: None,
+            compute_pipeline,
+            compute_shader_path,
+            shader_source_mtimes,
+            last_shader_poll: SystemTime::now(),
+            width,
+            height,
+        }
+    }
+
+    pub(crate) fn last_compute_time_ms(&self) -> Option<f32> {
+        self.last_compute_time_ms
+    }
+
+    pub(crate) fn resize(&mut self, width: u32, height: u32) {
+        // Every slot's texture is about to be replaced, so make sure the GPU
+        // is done reading the old one before that happens.
+        for submission_index in self.in_flight_submissions.iter_mut().filter_map(Option::take) {
+            self.device
+                .poll(Maintain::WaitForSubmissionIndex(submission_index));
+        }
+
+        self.width = width;
+        self.height = height;
+
+        for frame_data in self.frame_data.iter_mut() {
+            frame_data.texture = self.device.create_texture(&TextureDescriptor {
+                label: None,
+                size: Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: TextureFormat::Rgba8Unorm,
+                usage: TextureUsages::STORAGE_BINDING | TextureUsages::COPY_SRC,
+                view_formats: &[],
+            });
+
+            let texture_view = frame_data
+                .texture
+                .create_view(&TextureViewDescriptor::default());
+
+            frame_data.bind_group = self.device.create_bind_group(&BindGroupDescriptor {
+                label: None,
+                layout: &self.bind_group_layout,
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: frame_data.points_position_buffer.as_entire_binding(),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::TextureView(&texture_view),
+                    },
+                ],
+            });
+        }
+    }
+
+    // Runs one frame's CPU-side bookkeeping (shader reload, position
+    // update, GPU timing collection) and dispatches its compute pass into a
+    // fresh encoder. Blocks first if the slot about to be reused is still
+    // being read by a prior, not-yet-completed submission. The caller
+    // records whatever present/readback commands it needs into the returned
+    // encoder before passing it to `submit_frame`, so everything lands in
+    // the same submission.
+    pub(crate) fn begin_frame(&mut self, elapsed_secs: f32) -> (CommandEncoder, usize) {
+        self.poll_shader_reload();
+
+        let index = self.frame_data_index;
+
+        if let Some(submission_index) = self.in_flight_submissions[index].take() {
+            self.device
+                .poll(Maintain::WaitForSubmissionIndex(submission_index));
+        }
+
+        self.collect_gpu_timing(index);
+
+        let frame_data = &self.frame_data[index];
+        Self::update_points_position_buffer(&self.queue, frame_data, elapsed_secs);
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&CommandEncoderDescriptor { label: None });
+        self.dispatch_compute_pass(&mut encoder, frame_data);
+
+        (encoder, index)
+    }
+
+    pub(crate) fn frame_data(&self, index: usize) -> &FrameData {
+        &self.frame_data[index]
+    }
+
+    // Submits `encoder`, records the resulting `SubmissionIndex` so this
+    // slot's fence can be waited on the next time it comes around, kicks off
+    // this slot's GPU timing readback, and advances the round-robin index.
+    pub(crate) fn submit_frame(&mut self, encoder: CommandEncoder, index: usize) {
+        let submission_index = self.queue.submit(Some(encoder.finish()));
+        self.in_flight_submissions[index] = Some(submission_index);
+
+        Self::request_gpu_timing_readback(&self.frame_data[index]);
+        self.device.poll(Maintain::Poll);
+
+        self.frame_data_index = (index + 1) % self.frame_data.len();
+    }
+
+    // Allocates a readback buffer sized for the core's current width/height
+    // (rows padded up to `COPY_BYTES_PER_ROW_ALIGNMENT`, as required for
+    // `copy_texture_to_buffer`) and records the copy out of the given
+    // frame's texture into it.
+    pub(crate) fn begin_frame_capture(
+        &self,
+        encoder: &mut CommandEncoder,
+        frame_data_index: usize,
+    ) -> FrameCapture {
+        let width = self.width;
+        let height = self.height;
+        let unpadded_bytes_per_row = width * 4;
+        let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(COPY_BYTES_PER_ROW_ALIGNMENT)
+            * COPY_BYTES_PER_ROW_ALIGNMENT;
+
+        let buffer = Arc::new(self.device.create_buffer(&BufferDescriptor {
+            label: None,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            size: (padded_bytes_per_row * height) as u64,
+            mapped_at_creation: false,
+        }));
+
+        encoder.copy_texture_to_buffer(
+            self.frame_data[frame_data_index].texture.as_image_copy(),
+            TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        FrameCapture {
+            buffer,
+            width,
+            height,
+            padded_bytes_per_row,
+        }
+    }
+
+    fn create_bind_group_layout(device: &Device) -> BindGroupLayout {
+        device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: None,
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::COMPUTE,
+                    ty: BindingType::StorageTexture {
+                        access: StorageTextureAccess::WriteOnly,
+                        format: TextureFormat::Rgba8Unorm,
+                        view_dimension: TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+            ],
+        })
+    }
+
+    fn create_frame_data(
+        device: &Device,
+        width: u32,
+        height: u32,
+        bind_group_layout: &BindGroupLayout,
+        gpu_timing_supported: bool,
+        frames_in_flight: usize,
+    ) -> Vec<FrameData> {
+        (0..frames_in_flight).map(|_| {
+            let points_position_buffer = device.create_buffer(&BufferDescriptor {
+                label: None,
+                usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+                size: std::mem::size_of_val(STARTING_POSITION) as u64,
+                mapped_at_creation: false,
+            });
+
+            let texture = device.create_texture(&TextureDescriptor {
+                label: None,
+                size: Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: TextureDimension::D2,
+                format: TextureFormat::Rgba8Unorm,
+                usage: TextureUsages::STORAGE_BINDING | TextureUsages::COPY_SRC,
+                view_formats: &[],
+            });
+            let texture_view = texture.create_view(&TextureViewDescriptor::default());
+
+            let bind_group = device.create_bind_group(&BindGroupDescriptor {
+                label: None,
+                layout: bind_group_layout,
+                entries: &[
+                    BindGroupEntry {
+                        binding: 0,
+                        resource: points_position_buffer.as_entire_binding(),
+                    },
+                    BindGroupEntry {
+                        binding: 1,
+                        resource: BindingResource::TextureView(&texture_view),
+                    },
+                ],
+            });
+
+            let gpu_timing = gpu_timing_supported.then(|| Self::create_frame_gpu_timing(device));
+
+            FrameData {
+                points_position_buffer,
+                texture,
+                bind_group,
+                gpu_timing,
+            }
+        })
+        .collect()
+    }
+
+    fn create_frame_gpu_timing(device: &Device) -> FrameGpuTiming {
+        let query_set = device.create_query_set(&QuerySetDescriptor {
+            label: None,
+            ty: QueryType::Timestamp,
+            count: 2,
+        });
+
+        let resolve_buffer = device.create_buffer(&BufferDescriptor {
+            label: None,
+            usage: BufferUsages::QUERY_RESOLVE | BufferUsages::COPY_SRC,
+            size: 2 * std::mem::size_of::<u64>() as u64,
+            mapped_at_creation: false,
+        });
+
+        let readback_buffer = Arc::new(device.create_buffer(&BufferDescriptor {
+            label: None,
+            usage: BufferUsages::MAP_READ | BufferUsages::COPY_DST,
+            size: 2 * std::mem::size_of::<u64>() as u64,
+            mapped_at_creation: false,
+        }));
+
+        FrameGpuTiming {
+            query_set,
+            resolve_buffer,
+            readback_buffer,
+            pending_ticks: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    // Recursively splices `#include "file"` directives into `path`'s source,
+    // resolved relative to the including file. `chain` is the current
+    // inclusion stack, used to reject cycles while still allowing the same
+    // file to be included from separate branches (a diamond include).
+    // Every file actually read is recorded into `mtimes`, so the caller can
+    // later poll for changes across the whole resolved shader.
+    fn preprocess_shader_source(
+        path: &Path,
+        chain: &mut Vec<PathBuf>,
+        mtimes: &mut HashMap<PathBuf, SystemTime>,
+    ) -> Result<String, String> {
+        let path = path
+            .canonicalize()
+            .map_err(|error| format!("{}: {error}", path.display()))?;
+
+        if chain.contains(&path) {
+            return Err(format!("include cycle at {}", path.display()));
+        }
+
+        let metadata = std::fs::metadata(&path)
+            .map_err(|error| format!("{}: {error}", path.display()))?;
+        mtimes.insert(path.clone(), metadata.modified().unwrap());
+
+        let source =
+            std::fs::read_to_string(&path).map_err(|error| format!("{}: {error}", path.display()))?;
+
+        chain.push(path.clone());
+        let mut resolved = String::new();
+        for (line_index, line) in source.lines().enumerate() {
+            match Self::parse_include_directive(line) {
+                Some(included_name) => {
+                    let included_path = path.parent().unwrap_or(Path::new(".")).join(included_name);
+                    let included_source =
+                        Self::preprocess_shader_source(&included_path, chain, mtimes)
+                            .map_err(|error| {
+                                format!("{}:{}: {error}", path.display(), line_index + 1)
+                            })?;
+                    resolved.push_str(&included_source);
+                    resolved.push('\n');
+                }
+                None => {
+                    resolved.push_str(line);
+                    resolved.push('\n');
+                }
+            }
+        }
+        chain.pop();
+
+        Ok(resolved)
+    }
+
+    fn parse_include_directive(line: &str) -> Option<&str> {
+        line.trim()
+            .strip_prefix("#include")?
+            .trim()
+            .strip_prefix('"')?
+            .strip_suffix('"')
+    }
+
+    // Builds a fresh compute pipeline from `shader_path`, resolving
+    // `#include`s and capturing any compilation error instead of letting
+    // wgpu panic, so a shader typo can't take the window down.
+    fn try_build_compute_pipeline(
+        device: &Device,
+        bind_group_layout: &BindGroupLayout,
+        shader_path: &Path,
+    ) -> Result<(ComputePipeline, HashMap<PathBuf, SystemTime>), String> {
+        let mut chain = Vec::new();
+        let mut mtimes = HashMap::new();
+        let shader_source =
+            Self::preprocess_shader_source(shader_path, &mut chain, &mut mtimes)?;
+
+        device.push_error_scope(ErrorFilter::Validation);
+
+        let compute_shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: None,
+            source: ShaderSource::Glsl {
+                shader: Cow::Owned(shader_source),
+                stage: naga::ShaderStage::Compute,
+                defines: HashMap::default(),
+            },
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: None,
+            bind_group_layouts: &[bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let compute_pipeline = device.create_compute_pipeline(&ComputePipelineDescriptor {
+            label: None,
+            layout: Some(&pipeline_layout),
+            module: &compute_shader,
+            entry_point: None,
+            compilation_options: PipelineCompilationOptions::default(),
+            cache: None,
+        });
+
+        if let Some(error) = block_on(device.pop_error_scope()) {
+            return Err(error.to_string());
+        }
+
+        Ok((compute_pipeline, mtimes))
+    }
+
+    // Throttled to once per 100ms: if any resolved shader source file's
+    // mtime has changed, rebuild the pipeline. On failure the previous
+    // known-good pipeline keeps running.
+    fn poll_shader_reload(&mut self) {
+        let now = SystemTime::now();
+        let since_last_poll = now.duration_since(self.last_shader_poll).unwrap_or_default();
+        if since_last_poll < Duration::from_millis(100) {
+            return;
+        }
+        self.last_shader_poll = now;
+
+        let changed = self.shader_source_mtimes.iter().any(|(path, mtime)| {
+            std::fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .is_ok_and(|modified| modified != *mtime)
+        });
+        if !changed {
+            return;
+        }
+
+        match Self::try_build_compute_pipeline(
+            &self.device,
+            &self.bind_group_layout,
+            &self.compute_shader_path,
+        ) {
+            Ok((compute_pipeline, shader_source_mtimes)) => {
+                self.compute_pipeline = compute_pipeline;
+                self.shader_source_mtimes = shader_source_mtimes;
+            }
+            Err(error) => {
+                eprintln!("\nshader reload failed, keeping previous pipeline:\n{error}");
+            }
+        }
+    }
+
+    fn update_points_position_buffer(queue: &Queue, frame_data: &FrameData, elapsed_secs: f32) {
+        let r = -elapsed_secs * std::f32::consts::TAU / 4.0;
+        let mut sin_r = r.sin();
+        let mut cos_r = r.cos();
+        let scale_factor = (sin_r + 1.0) / 2.0;
+        sin_r *= scale_factor;
+        cos_r *= scale_factor;
+
+        let mut mapped = queue
+            .write_buffer_with(
+                &frame_data.points_position_buffer,
+                0,
+                NonZero::new(frame_data.points_position_buffer.size()).unwrap(),
+            )
+            .unwrap();
+
+        let mapped_slice = unsafe {
+            std::slice::from_raw_parts_mut(
+                mapped.as_mut_ptr().cast::<PointPosition>(),
+                (frame_data.points_position_buffer.size()
+                    / std::mem::size_of::<PointPosition>() as u64) as usize,
+            )
+        };
+
+        for (i, pos) in STARTING_POSITION.iter().enumerate() {
+            mapped_slice[i].0 = [
+                (pos.0[0] * cos_r - pos.0[1] * sin_r),
+                (pos.0[0] * sin_r + pos.0[1] * cos_r),
+            ];
+        }
+    }
+
+    fn dispatch_compute_pass(&self, encoder: &mut CommandEncoder, frame_data: &FrameData) {
+        let timestamp_writes =
+            frame_data
+                .gpu_timing
+                .as_ref()
+                .map(|timing| ComputePassTimestampWrites {
+                    query_set: &timing.query_set,
+                    beginning_of_pass_write_index: Some(0),
+                    end_of_pass_write_index: Some(1),
+                });
+
+        let mut compute_pass = encoder.begin_compute_pass(&ComputePassDescriptor {
+            label: None,
+            timestamp_writes,
+        });
+
+        compute_pass.set_pipeline(&self.compute_pipeline);
+        compute_pass.set_bind_group(0, &frame_data.bind_group, &[]);
+
+        let workgroup_size_x = 8;
+        let workgroup_size_y = 8;
+
+        let dispatch_x = self.width.div_ceil(workgroup_size_x);
+        let dispatch_y = self.height.div_ceil(workgroup_size_y);
+
+        compute_pass.dispatch_workgroups(dispatch_x, dispatch_y, 1);
+        drop(compute_pass);
+
+        if let Some(timing) = &frame_data.gpu_timing {
+            encoder.resolve_query_set(&timing.query_set, 0..2, &timing.resolve_buffer, 0);
+            encoder.copy_buffer_to_buffer(
+                &timing.resolve_buffer,
+                0,
+                &timing.readback_buffer,
+                0,
+                timing.readback_buffer.size(),
+            );
+        }
+    }
+
+    // Reads back whatever GPU timing the slot at `index` finished mapping
+    // since it was last used (i.e. one full round-robin cycle ago), if any.
+    fn collect_gpu_timing(&mut self, index: usize) {
+        let Some(timing) = &self.frame_data[index].gpu_timing else {
+            return;
+        };
+
+        let Some(ticks) = timing.pending_ticks.lock().unwrap().take() else {
+            return;
+        };
+
+        let elapsed_ticks = ticks[1].saturating_sub(ticks[0]);
+        let elapsed_ns = elapsed_ticks as f64 * self.timestamp_period_ns as f64;
+        self.last_compute_time_ms = Some((elapsed_ns / 1_000_000.0) as f32);
+    }
+
+    // Kicks off a non-blocking mapping of this frame's readback buffer so
+    // the ticks it holds are ready by the next time this slot comes around.
+    fn request_gpu_timing_readback(frame_data: &FrameData) {
+        let Some(timing) = &frame_data.gpu_timing else {
+            return;
+        };
+
+        let pending_ticks = timing.pending_ticks.clone();
+        let readback_buffer = timing.readback_buffer.clone();
+
+        timing
+            .readback_buffer
+            .slice(..)
+            .map_async(MapMode::Read, move |result| {
+                if result.is_err() {
+                    return;
+                }
+                let ticks = {
+                    let mapped = readback_buffer.slice(..).get_mapped_range();
+                    [
+                        u64::from_ne_bytes(mapped[0..8].try_into().unwrap()),
+                        u64::from_ne_bytes(mapped[8..16].try_into().unwrap()),
+                    ]
+                };
+                readback_buffer.unmap();
+                *pending_ticks.lock().unwrap() = Some(ticks);
+            });
+    }
+}
+
+// Strips `copy_texture_to_buffer`'s row alignment padding out of a mapped
+// readback buffer, returning tightly-packed RGBA8 pixels ready to encode.
+pub(crate) fn unpad_rgba8(mapped: &[u8], width: u32, height: u32, padded_bytes_per_row: u32) -> Vec<u8> {
+    let unpadded_bytes_per_row = (width * 4) as usize;
+    let mut pixels = vec![0u8; unpadded_bytes_per_row * height as usize];
+    for row in 0..height as usize {
+        let src_start = row * padded_bytes_per_row as usize;
+        let src_end = src_start + unpadded_bytes_per_row;
+        let dst_start = row * unpadded_bytes_per_row;
+        let dst_end = dst_start + unpadded_bytes_per_row;
+        pixels[dst_start..dst_end].copy_from_slice(&mapped[src_start..src_end]);
+    }
+    pixels
+}
+
+// Looks up `--flag value` in a raw argument list, used alongside the
+// existing ad hoc `--turbo` flag check for CLI knobs like
+// `--frames-in-flight` that take a value rather than being a bare switch.
+pub(crate) fn parse_arg_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|index| args.get(index + 1))
+        .map(String::as_str)
+}
@@ -1,12 +1,29 @@
-use std::{error::Error, io::Write as _};
+use std::{error::Error, io::Write as _, path::PathBuf};
 
 use winit::event_loop::EventLoop;
 
 mod app;
-use app::Application;
+use app::{Application, parse_arg_value, run_headless, DEFAULT_FRAMES_IN_FLIGHT};
 
 fn main() -> Result<(), Box<dyn Error>> {
     // env_logger::init();
+    let args: Vec<String> = std::env::args().collect();
+
+    if args.iter().any(|arg| arg == "--headless") {
+        let frames = parse_arg_value(&args, "--frames")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(60);
+        let out_dir = parse_arg_value(&args, "--out")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("out"));
+        let frames_in_flight = parse_arg_value(&args, "--frames-in-flight")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_FRAMES_IN_FLIGHT);
+
+        run_headless(frames, out_dir, frames_in_flight);
+        return Ok(());
+    }
+
     let event_loop = EventLoop::new().unwrap();
 
     println!("Running...");
@@ -1,20 +1,37 @@
-use std::{borrow::Cow, sync::Arc};
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+    sync::{Arc, mpsc},
+    thread,
+};
 
 use bytemuck::{Pod, Zeroable};
+use image::GenericImageView as _;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher as _};
 use pollster::block_on;
 use util::DeviceExt as _;
 use wgpu::*;
 use winit::{
     dpi::{LogicalSize, PhysicalSize},
+    event::ElementState,
     event_loop::ActiveEventLoop,
+    keyboard::KeyCode,
     window::{Window, WindowAttributes},
 };
 
+use super::camera::{
+    Camera, CameraController, CameraUniform, create_camera_bind_group_layout, mul_mat4,
+};
+
+const NUM_INSTANCES_PER_ROW: u32 = 10;
+const INSTANCE_SPACING: f32 = 0.6;
+
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
 struct Vertex {
     position: [f32; 3],
     color: [f32; 3],
+    tex_coords: [f32; 2],
 }
 
 impl Vertex {
@@ -33,6 +50,11 @@ impl Vertex {
                     shader_location: 1,
                     format: VertexFormat::Float32x3,
                 },
+                VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 3]>() as BufferAddress * 2,
+                    shader_location: 2,
+                    format: VertexFormat::Float32x2,
+                },
             ],
         }
     }
@@ -42,27 +64,125 @@ const VERTICES: &[Vertex] = &[
     Vertex {
         position: [-0.0868241, 0.49240386, 0.0],
         color: [1.0, 0.0, 0.0],
+        tex_coords: [0.4131759, 0.00759614],
     }, // A
     Vertex {
         position: [-0.49513406, 0.06958647, 0.0],
         color: [0.0, 1.0, 0.0],
+        tex_coords: [0.0048659444, 0.43041354],
     }, // B
     Vertex {
         position: [-0.21918549, -0.44939706, 0.0],
         color: [0.0, 0.0, 1.0],
+        tex_coords: [0.28081453, 0.949397],
     }, // C
     Vertex {
         position: [0.35966998, -0.3473291, 0.0],
         color: [1.0, 1.0, 1.0],
+        tex_coords: [0.85967, 0.84732914],
     }, // D
     Vertex {
         position: [0.44147372, 0.2347359, 0.0],
         color: [0.0, 0.0, 0.0],
+        tex_coords: [0.9414737, 0.2652641],
     }, // E
 ];
 
 const INDICES: &[u16] = &[0, 1, 4, 1, 2, 4, 2, 3, 4];
 
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+struct InstanceRaw {
+    model: [[f32; 4]; 4],
+}
+
+impl InstanceRaw {
+    fn desc() -> VertexBufferLayout<'static> {
+        VertexBufferLayout {
+            array_stride: std::mem::size_of::<InstanceRaw>() as BufferAddress,
+            step_mode: VertexStepMode::Instance,
+            attributes: &[
+                VertexAttribute {
+                    offset: 0,
+                    shader_location: 5,
+                    format: VertexFormat::Float32x4,
+                },
+                VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 4]>() as BufferAddress,
+                    shader_location: 6,
+                    format: VertexFormat::Float32x4,
+                },
+                VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 4]>() as BufferAddress * 2,
+                    shader_location: 7,
+                    format: VertexFormat::Float32x4,
+                },
+                VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 4]>() as BufferAddress * 3,
+                    shader_location: 8,
+                    format: VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
+
+struct InstanceData {
+    position: [f32; 3],
+    rotation_angle: f32,
+}
+
+impl InstanceData {
+    fn to_raw(&self) -> InstanceRaw {
+        let translation = [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [self.position[0], self.position[1], self.position[2], 1.0],
+        ];
+        let (sin, cos) = self.rotation_angle.sin_cos();
+        let rotation = [
+            [cos, 0.0, -sin, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [sin, 0.0, cos, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ];
+        InstanceRaw {
+            model: mul_mat4(&translation, &rotation),
+        }
+    }
+}
+
+fn create_instances() -> Vec<InstanceData> {
+    let half_extent = (NUM_INSTANCES_PER_ROW as f32 - 1.0) * INSTANCE_SPACING * 0.5;
+    (0..NUM_INSTANCES_PER_ROW)
+        .flat_map(|z| {
+            (0..NUM_INSTANCES_PER_ROW).map(move |x| {
+                let position = [
+                    x as f32 * INSTANCE_SPACING - half_extent,
+                    0.0,
+                    z as f32 * INSTANCE_SPACING - half_extent,
+                ];
+                let rotation_angle = if position == [0.0, 0.0, 0.0] {
+                    0.0
+                } else {
+                    (x + z) as f32 * 0.1
+                };
+                InstanceData {
+                    position,
+                    rotation_angle,
+                }
+            })
+        })
+        .collect()
+}
+
+struct FilterPass {
+    shader: ShaderModule,
+    pipeline: RenderPipeline,
+    bind_group: BindGroup,
+}
+
 #[allow(dead_code)]
 pub(crate) struct Context {
     window: Arc<Window>,
@@ -72,15 +192,48 @@ pub(crate) struct Context {
     device: Device,
     queue: Queue,
     shader: ShaderModule,
+    shader_path: PathBuf,
+    shader_watcher: RecommendedWatcher,
+    shader_reload_rx: mpsc::Receiver<notify::Result<notify::Event>>,
     pipeline_layout: PipelineLayout,
+    surface_format: TextureFormat,
     render_pipeline: RenderPipeline,
     config: SurfaceConfiguration,
     vertex_buffer: Buffer,
     index_buffer: Buffer,
+    num_indices: u32,
+    depth_view: TextureView,
+    diffuse_texture: Texture,
+    diffuse_bind_group: BindGroup,
+    camera: Camera,
+    camera_uniform: CameraUniform,
+    camera_buffer: Buffer,
+    camera_bind_group: BindGroup,
+    camera_controller: CameraController,
+    instances: Vec<InstanceData>,
+    instance_buffer: Buffer,
+    offscreen_texture: Texture,
+    offscreen_view: TextureView,
+    post_sampler: Sampler,
+    post_bind_group_layout: BindGroupLayout,
+    filter_chain: Vec<FilterPass>,
+    pending_loads: usize,
+    mesh_load_tx: mpsc::Sender<Result<LoadedMesh, String>>,
+    mesh_load_rx: mpsc::Receiver<Result<LoadedMesh, String>>,
+}
+
+/// CPU-side mesh data produced by a background loading thread, ready to be
+/// uploaded to the GPU by the thread that owns `Device`/`Queue`.
+struct LoadedMesh {
+    path: PathBuf,
+    vertices: Vec<Vertex>,
+    indices: Vec<u16>,
 }
 
 impl Context {
-    pub(crate) fn new(event_loop: &ActiveEventLoop) -> Self {
+    pub(crate) fn new(
+        event_loop: &ActiveEventLoop,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
         let window = Arc::new(
             event_loop
                 .create_window(WindowAttributes::default().with_inner_size(LogicalSize {
@@ -95,18 +248,33 @@ impl Context {
         size.height = size.height.max(1);
 
         let instance = Instance::new(&InstanceDescriptor {
-            backends: Backends::VULKAN,
+            backends: Backends::from_env().unwrap_or_else(Backends::all),
             flags: InstanceFlags::from_env_or_default(),
             backend_options: BackendOptions::from_env_or_default(),
         });
 
-        let surface = instance.create_surface(window.clone()).unwrap();
-        let adapter = block_on(instance.request_adapter(&RequestAdapterOptions {
+        let surface = instance.create_surface(window.clone())?;
+        let adapter = match block_on(instance.request_adapter(&RequestAdapterOptions {
             power_preference: PowerPreference::default(),
             force_fallback_adapter: false,
             compatible_surface: Some(&surface),
-        }))
-        .unwrap();
+        })) {
+            Some(adapter) => adapter,
+            None => {
+                log::warn!("No adapter for the preferred backend, retrying with a fallback");
+                block_on(instance.request_adapter(&RequestAdapterOptions {
+                    power_preference: PowerPreference::default(),
+                    force_fallback_adapter: true,
+                    compatible_surface: Some(&surface),
+                }))
+                .ok_or("no compatible graphics adapter found, even with fallback enabled")?
+            }
+        };
+        log::info!(
+            "Selected adapter: {} ({:?})",
+            adapter.get_info().name,
+            adapter.get_info().backend
+        );
 
         let (device, queue) = block_on(adapter.request_device(
             &DeviceDescriptor {
@@ -117,50 +285,147 @@ impl Context {
                 memory_hints: MemoryHints::MemoryUsage,
             },
             None,
-        ))
-        .unwrap();
+        ))?;
+
+        let shader_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/app/shader.wgsl");
 
         let shader = device.create_shader_module(ShaderModuleDescriptor {
             label: None,
             source: ShaderSource::Wgsl(Cow::Borrowed(include_str!("shader.wgsl"))),
         });
 
+        let (shader_reload_tx, shader_reload_rx) = mpsc::channel();
+        let mut shader_watcher = notify::recommended_watcher(shader_reload_tx).unwrap();
+        if let Err(error) = shader_watcher.watch(&shader_path, RecursiveMode::NonRecursive) {
+            log::warn!("Failed to watch {}: {error}", shader_path.display());
+        }
+
+        let diffuse_bytes = include_bytes!("happy-tree.png");
+        let diffuse_image = image::load_from_memory(diffuse_bytes).unwrap();
+        let diffuse_rgba = diffuse_image.to_rgba8();
+        let (diffuse_width, diffuse_height) = diffuse_image.dimensions();
+
+        let diffuse_texture = device.create_texture(&TextureDescriptor {
+            label: Some("Diffuse Texture"),
+            size: Extent3d {
+                width: diffuse_width,
+                height: diffuse_height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba8UnormSrgb,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        Self::write_diffuse_texture(
+            &queue,
+            &diffuse_texture,
+            &diffuse_rgba,
+            diffuse_width,
+            diffuse_height,
+        );
+
+        let diffuse_view = diffuse_texture.create_view(&TextureViewDescriptor::default());
+        let diffuse_sampler = device.create_sampler(&SamplerDescriptor {
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            address_mode_w: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Nearest,
+            mipmap_filter: FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let texture_bind_group_layout =
+            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("Texture Bind Group Layout"),
+                entries: &[
+                    BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Texture {
+                            sample_type: TextureSampleType::Float { filterable: true },
+                            view_dimension: TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            });
+
+        let diffuse_bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Diffuse Bind Group"),
+            layout: &texture_bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(&diffuse_view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(&diffuse_sampler),
+                },
+            ],
+        });
+
+        let camera = Camera {
+            eye: [0.0, 1.0, 2.0],
+            target: [0.0, 0.0, 0.0],
+            up: [0.0, 1.0, 0.0],
+            aspect: size.width as f32 / size.height as f32,
+            fovy: std::f32::consts::FRAC_PI_4,
+            znear: 0.1,
+            zfar: 100.0,
+        };
+        let mut camera_uniform = CameraUniform::new();
+        camera_uniform.update_view_proj(&camera);
+
+        let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Camera Buffer"),
+            contents: bytemuck::cast_slice(&[camera_uniform]),
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+        });
+
+        let camera_bind_group_layout = create_camera_bind_group_layout(&device);
+        let camera_bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Camera Bind Group"),
+            layout: &camera_bind_group_layout,
+            entries: &[BindGroupEntry {
+                binding: 0,
+                resource: camera_buffer.as_entire_binding(),
+            }],
+        });
+
+        let camera_controller = CameraController::new(0.05);
+
         let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
             label: None,
-            bind_group_layouts: &[],
+            bind_group_layouts: &[&texture_bind_group_layout, &camera_bind_group_layout],
             push_constant_ranges: &[],
         });
 
         let swapchain_capabilities = surface.get_capabilities(&adapter);
-        let swapchain_format = swapchain_capabilities.formats[0];
+        let surface_format = swapchain_capabilities.formats[0];
 
-        let render_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
-            label: None,
-            layout: Some(&pipeline_layout),
-            vertex: VertexState {
-                module: &shader,
-                entry_point: Some("vs_main"),
-                buffers: &[Vertex::desc()],
-                compilation_options: Default::default(),
-            },
-            fragment: Some(FragmentState {
-                module: &shader,
-                entry_point: Some("fs_main"),
-                compilation_options: Default::default(),
-                targets: &[Some(swapchain_format.into())],
-            }),
-            primitive: PrimitiveState::default(),
-            depth_stencil: None,
-            multisample: MultisampleState::default(),
-            multiview: None,
-            cache: None,
-        });
+        let render_pipeline =
+            Self::build_render_pipeline(&device, &shader, &pipeline_layout, surface_format);
 
         let config = surface
             .get_default_config(&adapter, size.width, size.height)
             .unwrap();
         surface.configure(&device, &config);
 
+        let depth_view = Self::create_depth_view(&device, config.width, config.height);
+
         let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Vertex Buffer"),
             contents: bytemuck::cast_slice(VERTICES),
@@ -172,8 +437,63 @@ impl Context {
             contents: bytemuck::cast_slice(INDICES),
             usage: BufferUsages::INDEX,
         });
+        let num_indices = INDICES.len() as u32;
+
+        let (mesh_load_tx, mesh_load_rx) = mpsc::channel();
+
+        let instances = create_instances();
+        let instance_data: Vec<InstanceRaw> = instances.iter().map(InstanceData::to_raw).collect();
+        let instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Instance Buffer"),
+            contents: bytemuck::cast_slice(&instance_data),
+            usage: BufferUsages::VERTEX,
+        });
+
+        let (offscreen_texture, offscreen_view) =
+            Self::create_offscreen_target(&device, surface_format, config.width, config.height);
 
-        Context {
+        let post_sampler = device.create_sampler(&SamplerDescriptor {
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            address_mode_w: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            mipmap_filter: FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let post_bind_group_layout =
+            device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("Post Bind Group Layout"),
+                entries: &[
+                    BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Texture {
+                            sample_type: TextureSampleType::Float { filterable: true },
+                            view_dimension: TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: ShaderStages::FRAGMENT,
+                        ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            });
+
+        let filter_chain = vec![Self::create_filter_pass(
+            &device,
+            &post_bind_group_layout,
+            &post_sampler,
+            &offscreen_view,
+            surface_format,
+        )];
+
+        Ok(Context {
             window,
             instance,
             surface,
@@ -181,15 +501,369 @@ impl Context {
             device,
             queue,
             shader,
+            shader_path,
+            shader_watcher,
+            shader_reload_rx,
             pipeline_layout,
+            surface_format,
             render_pipeline,
             config,
             vertex_buffer,
             index_buffer,
+            num_indices,
+            depth_view,
+            diffuse_texture,
+            diffuse_bind_group,
+            camera,
+            camera_uniform,
+            camera_buffer,
+            camera_bind_group,
+            camera_controller,
+            instances,
+            instance_buffer,
+            offscreen_texture,
+            offscreen_view,
+            post_sampler,
+            post_bind_group_layout,
+            filter_chain,
+            pending_loads: 0,
+            mesh_load_tx,
+            mesh_load_rx,
+        })
+    }
+
+    fn create_offscreen_target(
+        device: &Device,
+        format: TextureFormat,
+        width: u32,
+        height: u32,
+    ) -> (Texture, TextureView) {
+        let offscreen_texture = device.create_texture(&TextureDescriptor {
+            label: Some("Offscreen Texture"),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let offscreen_view = offscreen_texture.create_view(&TextureViewDescriptor::default());
+        (offscreen_texture, offscreen_view)
+    }
+
+    fn create_filter_pass(
+        device: &Device,
+        bind_group_layout: &BindGroupLayout,
+        sampler: &Sampler,
+        source_view: &TextureView,
+        target_format: TextureFormat,
+    ) -> FilterPass {
+        let shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("Post Shader"),
+            source: ShaderSource::Wgsl(Cow::Borrowed(include_str!("post.wgsl"))),
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Post Pipeline Layout"),
+            bind_group_layouts: &[bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Post Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(target_format.into())],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+
+        let bind_group = device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Post Bind Group"),
+            layout: bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(source_view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(sampler),
+                },
+            ],
+        });
+
+        FilterPass {
+            shader,
+            pipeline,
+            bind_group,
         }
     }
 
+    pub(crate) fn window(&self) -> &Window {
+        &self.window
+    }
+
+    pub(crate) fn process_keyboard(&mut self, key: KeyCode, state: ElementState) -> bool {
+        self.camera_controller.process_keyboard(key, state)
+    }
+
+    fn build_render_pipeline(
+        device: &Device,
+        shader: &ShaderModule,
+        pipeline_layout: &PipelineLayout,
+        surface_format: TextureFormat,
+    ) -> RenderPipeline {
+        device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: None,
+            layout: Some(pipeline_layout),
+            vertex: VertexState {
+                module: shader,
+                entry_point: Some("vs_main"),
+                buffers: &[Vertex::desc(), InstanceRaw::desc()],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(FragmentState {
+                module: shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(surface_format.into())],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: Some(DepthStencilState {
+                format: TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: CompareFunction::Less,
+                stencil: Default::default(),
+                bias: Default::default(),
+            }),
+            multisample: MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    /// Polls the shader watcher channel and, on a change event, recompiles
+    /// the shader and rebuilds the pipeline in place. Returns `true` if a
+    /// reload was attempted (whether or not it succeeded), so the caller
+    /// can decide to request a redraw.
+    pub(crate) fn poll_shader_reload(&mut self) -> bool {
+        let mut reload_requested = false;
+        while let Ok(event) = self.shader_reload_rx.try_recv() {
+            if matches!(event, Ok(event) if event.kind.is_modify()) {
+                reload_requested = true;
+            }
+        }
+
+        if !reload_requested {
+            return false;
+        }
+
+        let Ok(source) = std::fs::read_to_string(&self.shader_path) else {
+            return true;
+        };
+
+        self.device.push_error_scope(ErrorFilter::Validation);
+
+        let shader = self.device.create_shader_module(ShaderModuleDescriptor {
+            label: None,
+            source: ShaderSource::Wgsl(Cow::Owned(source)),
+        });
+        let render_pipeline = Self::build_render_pipeline(
+            &self.device,
+            &shader,
+            &self.pipeline_layout,
+            self.surface_format,
+        );
+
+        if let Some(error) = block_on(self.device.pop_error_scope()) {
+            log::error!("Shader reload failed, keeping previous pipeline: {error}");
+        } else {
+            self.shader = shader;
+            self.render_pipeline = render_pipeline;
+            log::info!("Reloaded shader.wgsl");
+        }
+
+        true
+    }
+
+    /// Enqueues an off-thread load of the mesh at `path`, decoding it without
+    /// blocking the event loop. The result is picked up by `poll_pending_loads`.
+    pub(crate) fn load_mesh(&mut self, path: PathBuf) {
+        let tx = self.mesh_load_tx.clone();
+        self.pending_loads += 1;
+        thread::spawn(move || {
+            let result = Self::parse_mesh_file(&path)
+                .map(|(vertices, indices)| LoadedMesh { path, vertices, indices })
+                .map_err(|error| error.to_string());
+            let _ = tx.send(result);
+        });
+    }
+
+    /// Uploads any meshes that finished loading on a background thread.
+    /// Returns `true` if a redraw is needed.
+    pub(crate) fn poll_pending_loads(&mut self) -> bool {
+        let mut uploaded = false;
+        while let Ok(result) = self.mesh_load_rx.try_recv() {
+            self.pending_loads -= 1;
+            match result {
+                Ok(mesh) => {
+                    self.vertex_buffer =
+                        self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                            label: Some("Vertex Buffer"),
+                            contents: bytemuck::cast_slice(&mesh.vertices),
+                            usage: BufferUsages::VERTEX,
+                        });
+                    self.index_buffer =
+                        self.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                            label: Some("Index Buffer"),
+                            contents: bytemuck::cast_slice(&mesh.indices),
+                            usage: BufferUsages::INDEX,
+                        });
+                    self.num_indices = mesh.indices.len() as u32;
+                    log::info!("Loaded mesh {}", mesh.path.display());
+                    uploaded = true;
+                }
+                Err(error) => log::warn!("Mesh load failed: {error}"),
+            }
+        }
+        uploaded
+    }
+
+    /// Parses a minimal OBJ subset (`v`, `vt`, `f`) into a flat, unindexed
+    /// triangle list. Faces are assumed to already be triangulated.
+    fn parse_mesh_file(path: &Path) -> Result<(Vec<Vertex>, Vec<u16>), Box<dyn std::error::Error>> {
+        let contents = std::fs::read_to_string(path)?;
+        let mut positions = Vec::new();
+        let mut tex_coords = Vec::new();
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+
+        for line in contents.lines() {
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("v") => {
+                    let c: Vec<f32> = tokens.map(str::parse).collect::<Result<_, _>>()?;
+                    positions.push([c[0], c[1], c[2]]);
+                }
+                Some("vt") => {
+                    let c: Vec<f32> = tokens.map(str::parse).collect::<Result<_, _>>()?;
+                    tex_coords.push([c[0], c[1]]);
+                }
+                Some("f") => {
+                    for token in tokens {
+                        let mut parts = token.split('/');
+                        let position_index: usize = parts.next().ok_or("malformed face")?.parse()?;
+                        let tex_coord_index = parts
+                            .next()
+                            .filter(|s| !s.is_empty())
+                            .map(str::parse)
+                            .transpose()?
+                            .unwrap_or(position_index);
+                        let tex_coords =
+                            tex_coords.get(tex_coord_index - 1).copied().unwrap_or([0.0; 2]);
+                        vertices.push(Vertex {
+                            position: positions[position_index - 1],
+                            color: [1.0, 1.0, 1.0],
+                            tex_coords,
+                        });
+                        indices.push((vertices.len() - 1) as u16);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok((vertices, indices))
+    }
+
+    fn write_diffuse_texture(
+        queue: &Queue,
+        diffuse_texture: &Texture,
+        diffuse_rgba: &[u8],
+        width: u32,
+        height: u32,
+    ) {
+        let unpadded_bytes_per_row = width * 4;
+        let align = COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(align) * align;
+
+        let mut padded_data = vec![0u8; (padded_bytes_per_row * height) as usize];
+        for row in 0..height as usize {
+            let src_start = row * unpadded_bytes_per_row as usize;
+            let dst_start = row * padded_bytes_per_row as usize;
+            let src_end = src_start + unpadded_bytes_per_row as usize;
+            let dst_end = dst_start + unpadded_bytes_per_row as usize;
+            padded_data[dst_start..dst_end].copy_from_slice(&diffuse_rgba[src_start..src_end]);
+        }
+
+        queue.write_texture(
+            TexelCopyTextureInfo {
+                texture: diffuse_texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            &padded_data,
+            TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_bytes_per_row),
+                rows_per_image: Some(height),
+            },
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+
+    fn create_depth_view(device: &Device, width: u32, height: u32) -> TextureView {
+        let depth_texture = device.create_texture(&TextureDescriptor {
+            label: Some("Depth Texture"),
+            size: Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Depth32Float,
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+
+        depth_texture.create_view(&TextureViewDescriptor::default())
+    }
+
     pub(crate) fn redraw(&mut self) {
+        self.camera_controller.update_camera(&mut self.camera);
+        self.camera_uniform.update_view_proj(&self.camera);
+        self.queue.write_buffer(
+            &self.camera_buffer,
+            0,
+            bytemuck::cast_slice(&[self.camera_uniform]),
+        );
+
         let frame = self.surface.get_current_texture().unwrap();
         let view = frame.texture.create_view(&TextureViewDescriptor::default());
         let mut encoder = self
@@ -200,7 +874,7 @@ impl Context {
             let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
                 label: None,
                 color_attachments: &[Some(RenderPassColorAttachment {
-                    view: &view,
+                    view: &self.offscreen_view,
                     resolve_target: None,
                     ops: Operations {
                         load: LoadOp::Clear(Color {
@@ -212,15 +886,50 @@ impl Context {
                         store: StoreOp::Store,
                     },
                 })],
-                depth_stencil_attachment: None,
+                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                    view: &self.depth_view,
+                    depth_ops: Some(Operations {
+                        load: LoadOp::Clear(1.0),
+                        store: StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
                 timestamp_writes: None,
                 occlusion_query_set: None,
             });
 
             render_pass.set_pipeline(&self.render_pipeline);
+            render_pass.set_bind_group(0, &self.diffuse_bind_group, &[]);
+            render_pass.set_bind_group(1, &self.camera_bind_group, &[]);
             render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+            render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
             render_pass.set_index_buffer(self.index_buffer.slice(..), IndexFormat::Uint16);
-            render_pass.draw_indexed(0..INDICES.len() as u32, 0, 0..1);
+            render_pass.draw_indexed(0..self.num_indices, 0, 0..self.instances.len() as u32);
+        }
+
+        // Every pass in the chain samples the offscreen scene texture through
+        // its own bind group and renders straight into the swapchain view.
+        // Passes do not feed one another through intermediate textures, so
+        // with more than one pass only the last pass's output stays visible.
+        for filter_pass in &self.filter_chain {
+            let mut post_pass = encoder.begin_render_pass(&RenderPassDescriptor {
+                label: Some("Post Pass"),
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Clear(Color::BLACK),
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            post_pass.set_pipeline(&filter_pass.pipeline);
+            post_pass.set_bind_group(0, &filter_pass.bind_group, &[]);
+            post_pass.draw(0..3, 0..1);
         }
 
         self.queue.submit(Some(encoder.finish()));
@@ -231,6 +940,26 @@ impl Context {
         self.config.width = new_size.width.max(1);
         self.config.height = new_size.height.max(1);
         self.surface.configure(&self.device, &self.config);
+        self.depth_view =
+            Self::create_depth_view(&self.device, self.config.width, self.config.height);
+        self.camera.aspect = self.config.width as f32 / self.config.height as f32;
+
+        let (offscreen_texture, offscreen_view) = Self::create_offscreen_target(
+            &self.device,
+            self.surface_format,
+            self.config.width,
+            self.config.height,
+        );
+        self.offscreen_texture = offscreen_texture;
+        self.offscreen_view = offscreen_view;
+        self.filter_chain = vec![Self::create_filter_pass(
+            &self.device,
+            &self.post_bind_group_layout,
+            &self.post_sampler,
+            &self.offscreen_view,
+            self.surface_format,
+        )];
+
         self.window.request_redraw();
     }
 }
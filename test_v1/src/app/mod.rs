@@ -1,6 +1,13 @@
 
-use winit::{application::ApplicationHandler, event::WindowEvent, event_loop::ActiveEventLoop, keyboard::KeyCode, window::WindowId};
+use winit::{
+    application::ApplicationHandler,
+    event::WindowEvent,
+    event_loop::ActiveEventLoop,
+    keyboard::{KeyCode, PhysicalKey},
+    window::WindowId,
+};
 
+mod camera;
 mod context;
 use context::Context;
 
@@ -12,7 +19,13 @@ pub(crate) struct Application {
 impl ApplicationHandler for Application {
     fn resumed(&mut self, event_loop: &ActiveEventLoop) {
         if self.context.is_none() {
-            self.context = Some(Context::new(event_loop))
+            match Context::new(event_loop) {
+                Ok(context) => self.context = Some(context),
+                Err(error) => {
+                    log::error!("Failed to initialize graphics context: {error}");
+                    event_loop.exit();
+                }
+            }
         }
     }
 
@@ -23,12 +36,22 @@ impl ApplicationHandler for Application {
         event: WindowEvent,
     ) {
         let context = self.context.as_mut().unwrap();
+        if context.poll_shader_reload() {
+            context.window().request_redraw();
+        }
+        if context.poll_pending_loads() {
+            context.window().request_redraw();
+        }
         match event {
             WindowEvent::Resized(new_size) => context.resize(new_size),
             WindowEvent::RedrawRequested => context.redraw(),
             WindowEvent::KeyboardInput { event, .. } => {
                 if event.physical_key == KeyCode::Escape && !event.repeat {
                     event_loop.exit();
+                } else if let PhysicalKey::Code(key) = event.physical_key {
+                    if context.process_keyboard(key, event.state) {
+                        context.window().request_redraw();
+                    }
                 }
             }
             WindowEvent::CloseRequested => event_loop.exit(),